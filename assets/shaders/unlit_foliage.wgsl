@@ -0,0 +1,25 @@
+#import bevy_pbr::forward_io::VertexOutput
+
+struct UnlitFoliage {
+    base_color: vec4<f32>,
+    alpha_cutoff: f32,
+};
+
+@group(2) @binding(0) var<uniform> material: UnlitFoliage;
+@group(2) @binding(1) var base_color_texture: texture_2d<f32>;
+@group(2) @binding(2) var base_color_sampler: sampler;
+
+@fragment
+fn fragment(in: VertexOutput) -> @location(0) vec4<f32> {
+    var color = material.base_color;
+
+#ifdef VERTEX_UVS_A
+    color *= textureSample(base_color_texture, base_color_sampler, in.uv);
+#endif
+
+    if (color.a < material.alpha_cutoff) {
+        discard;
+    }
+
+    return color;
+}
@@ -1,5 +1,6 @@
 struct Wind {
     direction: vec2<f32>,
+    vertical: f32,
     strength: f32,
     noise_scale: f32,
     scroll_speed: f32,
@@ -18,16 +19,94 @@ struct Wind {
     enable_edge_correction: u32,
     edge_correction_factor: f32,
     lod_threshold: f32,
+    enable_flow_field: u32,
+    flow_scale: f32,
+    flow_scroll_speed: f32,
+    enable_noise_volume: u32,
+    volume_speed: f32,
+    phase_seed: u32,
+};
+
+const MAX_WIND_ZONES: u32 = 8u;
+
+struct WindZone {
+    zone_from_world: mat4x4<f32>,
+    extents: vec4<f32>,
+    direction: vec2<f32>,
+    direction_weight: f32,
+    shape: u32,
+    inner: f32,
+    strength: f32,
+};
+
+const MAX_WIND_IMPULSES: u32 = 8u;
+
+struct WindImpulse {
+    origin: vec3<f32>,
+    age: f32,
+    radius: f32,
+    strength: f32,
+    speed: f32,
+    decay: f32,
+};
+
+const MAX_FOLIAGE_INTERACTORS: u32 = 16u;
+
+struct FoliageInteractor {
+    position: vec3<f32>,
+    radius: f32,
+    strength: f32,
+    height: f32,
+};
+
+struct Trample {
+    origin: vec2<f32>,
+    size: f32,
+    strength: f32,
+};
+
+const MAX_WIND_PHASES: u32 = 32u;
+
+struct WindPhases {
+    macro_offset: vec2<f32>,
+    flow_offset: vec2<f32>,
+    micro_offset: f32,
+    s_curve_phase: f32,
+    bop_phase: f32,
+    volume_offset: f32,
+    direction: vec2<f32>,
+    strength: f32,
 };
 
 struct WindMaterialUniform {
     wind: Wind,
+    zones: array<WindZone, MAX_WIND_ZONES>,
+    noise_texture_size: vec2<f32>,
+    zone_count: u32,
+    phase_slot: u32,
+};
+
+// Same for every material, see `WindShared`.
+struct WindShared {
+    phases: array<WindPhases, MAX_WIND_PHASES>,
+    impulses: array<WindImpulse, MAX_WIND_IMPULSES>,
+    interactors: array<FoliageInteractor, MAX_FOLIAGE_INTERACTORS>,
+    trample: Trample,
+    impulse_count: u32,
+    interactor_count: u32,
 };
 
 struct BindlessWindIndices {
     material: u32,
     noise_texture: u32,
     noise_texture_sampler: u32,
+    flow_texture: u32,
+    flow_texture_sampler: u32,
+    trample_texture: u32,
+    trample_texture_sampler: u32,
+    noise_volume: u32,
+    noise_volume_sampler: u32,
+    shared_buffer: u32,
 }
 
 
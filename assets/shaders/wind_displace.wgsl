@@ -4,7 +4,7 @@
     mesh_view_bindings::{view,globals}
 };
 
-#import "./shaders/wind.wgsl"::Wind
+#import "./shaders/wind.wgsl"::{Wind, WindZone, MAX_WIND_ZONES, WindImpulse, MAX_WIND_IMPULSES, FoliageInteractor, MAX_FOLIAGE_INTERACTORS, Trample, WindPhases}
 
 
 struct SampledNoise {
@@ -21,8 +21,10 @@ struct DisplacedVertex {
 struct InstanceInfo {
     world_from_local: mat4x4<f32>,
     instance_position: vec4<f32>,
-    wrapped_time: f32,
-    instance_index: u32
+    phases: WindPhases,
+    instance_index: u32,
+    // Offset from impulses, interactors and trampling at the tip, scaled down towards the root with the c-curve.
+    external_displacement: vec3<f32>,
 }
 
 fn calculate_vertex_displacement(
@@ -37,14 +39,14 @@ fn calculate_vertex_displacement(
     let twisted_local_pos = calculate_twist(wind, noise.macro_noise, c_curve_shape, local_pos);
 
     let macro_displacement = (noise.macro_noise * 2.0 - 1.0) * wind.strength * c_curve_shape;
-    let horizontal_dir = vec3<f32>(wind.direction.x, 0.0, wind.direction.y);
-    var total_world_offset = horizontal_dir * macro_displacement;
+    let wind_dir = calculate_wind_direction(wind);
+    var total_world_offset = wind_dir * macro_displacement + instance.external_displacement * c_curve_shape;
 
     if (lod_fade > 0.0) {
         let micro_displacement = (noise.micro_noise * 2.0 - 1.0) * wind.micro_strength * c_curve_shape;
-        let micro_wind = horizontal_dir * micro_displacement;
-        let s_curve = calculate_s_curve_displacement(wind, c_curve_shape, normalized_height, instance.wrapped_time, noise.phase_noise.x);
-        let bop = calculate_bop_displacement(wind, c_curve_shape, instance.wrapped_time, noise.phase_noise.y);
+        let micro_wind = wind_dir * micro_displacement;
+        let s_curve = calculate_s_curve_displacement(wind, c_curve_shape, normalized_height, instance.phases.s_curve_phase, noise.phase_noise.x);
+        let bop = calculate_bop_displacement(wind, c_curve_shape, instance.phases.bop_phase, noise.phase_noise.y);
         total_world_offset += (micro_wind + s_curve + bop) * lod_fade;
     }
 
@@ -123,6 +125,197 @@ fn displace_vertex_and_calc_normal(
     return out;
 }
 
+// Mip level matching the noise footprint of a pixel at `dist_to_camera`, the noise is sampled once
+// per instance in the vertex shader so there are no screen space derivatives to rely on.
+fn calculate_noise_lod(
+    dist_to_camera: f32,
+    noise_scale: f32,
+    texture_size: vec2<f32>,
+) -> f32 {
+    let is_orthographic = view.clip_from_view[3][3] == 1.0;
+    let depth = select(dist_to_camera, 1.0, is_orthographic);
+    let world_per_pixel = 2.0 * depth / (view.clip_from_view[1][1] * view.viewport.w);
+    let texels_per_pixel = world_per_pixel * noise_scale * max(texture_size.x, texture_size.y);
+
+    return max(log2(max(texels_per_pixel, 0.0001)), 0.0);
+}
+
+// Walks through the time slices of the noise volume, the depth wraps so the shapes loop.
+fn calculate_volume_coord(macro_coord: vec2<f32>, phases: WindPhases) -> vec3<f32> {
+    return vec3<f32>(macro_coord, phases.volume_offset);
+}
+
+// The world position is the default as the instance index depends on batching.
+fn calculate_phase_coord(
+    wind: Wind,
+    position: vec3<f32>,
+    instance_index: u32,
+    texture_size: vec2<f32>,
+) -> vec2<f32> {
+    if (wind.phase_seed == 1u) {
+        let size = max(vec2<u32>(texture_size), vec2<u32>(1u));
+        let texel = vec2<u32>(instance_index % size.x, (instance_index / size.x) % size.y);
+        return (vec2<f32>(texel) + 0.5) / vec2<f32>(size);
+    }
+
+    return position.xz * 7.31;
+}
+
+fn apply_flow_field(
+    wind: Wind,
+    flow_sample: vec2<f32>,
+) -> Wind {
+    var flow_wind = wind;
+    let flow = flow_sample * 2.0 - 1.0;
+
+    if (length(flow) > 0.0001) {
+        flow_wind.direction = normalize(flow);
+    }
+
+    return flow_wind;
+}
+
+fn apply_wind_zones(
+    base_wind: Wind,
+    wind_zones: array<WindZone, MAX_WIND_ZONES>,
+    zone_count: u32,
+    position: vec3<f32>,
+) -> Wind {
+    var wind = base_wind;
+    var zones = wind_zones;
+    var strength_scale = 1.0;
+    var direction = wind.direction;
+    var direction_weight = 0.0;
+
+    for (var i = 0u; i < min(zone_count, MAX_WIND_ZONES); i++) {
+        let zone = zones[i];
+        let weight = 1.0 - smoothstep(zone.inner, 1.0, calculate_wind_zone_distance(zone, position));
+
+        strength_scale *= mix(1.0, zone.strength, weight);
+        direction = mix(direction, zone.direction, weight * zone.direction_weight);
+        direction_weight += weight * zone.direction_weight;
+    }
+
+    // Without a zone turning it, the direction keeps its length like outside of any zone.
+    if (direction_weight > 0.0 && length(direction) > 0.0001) {
+        wind.direction = normalize(direction);
+    }
+    wind.strength *= strength_scale;
+    wind.micro_strength *= strength_scale;
+
+    return wind;
+}
+
+fn calculate_impulse_displacement(
+    wind_impulses: array<WindImpulse, MAX_WIND_IMPULSES>,
+    impulse_count: u32,
+    position: vec3<f32>,
+) -> vec3<f32> {
+    var impulses = wind_impulses;
+    var displacement = vec3<f32>(0.0);
+
+    for (var i = 0u; i < min(impulse_count, MAX_WIND_IMPULSES); i++) {
+        let impulse = impulses[i];
+        let offset = position.xz - impulse.origin.xz;
+        let impulse_distance = length(offset);
+
+        if (impulse_distance > impulse.radius || impulse_distance < 0.0001) {
+            continue;
+        }
+
+        // Gaussian ring around the expanding front, fading out over time and towards the radius.
+        let front = impulse.speed * impulse.age;
+        let ring_width = max(impulse.radius * 0.15, 0.0001);
+        let front_distance = impulse_distance - front;
+        let ring = exp(-(front_distance * front_distance) / (2.0 * ring_width * ring_width));
+        let edge_fade = 1.0 - clamp((impulse_distance / impulse.radius - 0.75) / 0.25, 0.0, 1.0);
+        let fade = exp(-impulse.decay * impulse.age) * edge_fade;
+
+        let direction = offset / impulse_distance;
+        displacement += vec3<f32>(direction.x, 0.0, direction.y) * impulse.strength * ring * fade;
+    }
+
+    return displacement;
+}
+
+fn calculate_interactor_displacement(
+    foliage_interactors: array<FoliageInteractor, MAX_FOLIAGE_INTERACTORS>,
+    interactor_count: u32,
+    position: vec3<f32>,
+) -> vec3<f32> {
+    var interactors = foliage_interactors;
+    var displacement = vec3<f32>(0.0);
+
+    for (var i = 0u; i < min(interactor_count, MAX_FOLIAGE_INTERACTORS); i++) {
+        let interactor = interactors[i];
+        let offset = position.xz - interactor.position.xz;
+        let interactor_distance = length(offset);
+
+        if (interactor_distance > interactor.radius) {
+            continue;
+        }
+
+        let radial = 1.0 - smoothstep(0.0, interactor.radius, interactor_distance);
+        let vertical = 1.0 - smoothstep(0.0, interactor.height, abs(position.y - interactor.position.y));
+        let weight = radial * vertical * interactor.strength;
+
+        var direction = vec2<f32>(0.0);
+        if (interactor_distance > 0.0001) {
+            direction = offset / interactor_distance;
+        }
+
+        // Pushed outwards and pressed down, blades right under the interactor only flatten.
+        displacement += vec3<f32>(direction.x, -0.5, direction.y) * weight;
+    }
+
+    return displacement;
+}
+
+fn calculate_trample_uv(
+    trample: Trample,
+    position: vec3<f32>,
+) -> vec2<f32> {
+    return (position.xz - trample.origin) / trample.size + 0.5;
+}
+
+fn calculate_trample_displacement(
+    trample: Trample,
+    trample_sample: vec4<f32>,
+) -> vec3<f32> {
+    let direction = trample_sample.rg * 2.0 - 1.0;
+    return vec3<f32>(direction.x, -1.0, direction.y) * trample_sample.b * trample.strength;
+}
+
+fn calculate_wind_zone_distance(
+    zone: WindZone,
+    position: vec3<f32>,
+) -> f32 {
+    let local_pos = (zone.zone_from_world * vec4<f32>(position, 1.0)).xyz;
+    var zone_distance = 2.0;
+
+    switch zone.shape {
+        // Sphere
+        case 0u: {
+            zone_distance = length(local_pos) / zone.extents.x;
+        }
+        // Box
+        case 1u: {
+            let box_distance = abs(local_pos) / zone.extents.xyz;
+            zone_distance = max(box_distance.x, max(box_distance.y, box_distance.z));
+        }
+        // Cone, opening along -z
+        default: {
+            let along = -local_pos.z;
+            if (along >= 0.0) {
+                let cone_radius = max(along * zone.extents.y, 0.0001);
+                zone_distance = max(along / zone.extents.x, length(local_pos.xy) / cone_radius);
+            }
+        }
+    }
+
+    return zone_distance;
+}
+
 fn calculate_edge_correction(
     world_pos: vec3<f32>,
     local_pos: vec3<f32>,
@@ -152,37 +345,39 @@ fn calculate_main_wind_displacement(
     let micro_displacement = (micro_noise * 2.0 - 1.0) * wind.micro_strength * c_curve_shape;
 
     let combined_displacement = macro_displacement + micro_displacement;
-    let horizontal_dir = vec3<f32>(wind.direction.x, 0.0, wind.direction.y);
 
-    return horizontal_dir * combined_displacement;
+    return calculate_wind_direction(wind) * combined_displacement;
+}
+
+fn calculate_wind_direction(wind: Wind) -> vec3<f32> {
+    return vec3<f32>(wind.direction.x, wind.vertical, wind.direction.y);
 }
 
 fn calculate_s_curve_displacement(
     wind: Wind,
     c_curve_shape: f32,
     normalized_height: f32,
-    wrapped_time: f32,
+    s_curve_phase: f32,
     s_curve_seed: f32,
 ) -> vec3<f32> {
     let s_curve_phase_offset = s_curve_seed * 6.28318;
-    let s_curve_anim = sin(wrapped_time * wind.s_curve_speed + s_curve_phase_offset);
+    let s_curve_anim = sin(s_curve_phase + s_curve_phase_offset);
     let s_curve_wiggles = sin(normalized_height * wind.s_curve_frequency);
 
     let final_s_curve_shape = c_curve_shape + (s_curve_wiggles * wind.s_curve_strength * c_curve_shape);
     let s_curve_amount = s_curve_anim * wind.s_curve_strength * final_s_curve_shape;
-    let horizontal_dir = vec3<f32>(wind.direction.x, 0.0, wind.direction.y);
 
-    return horizontal_dir * s_curve_amount;
+    return calculate_wind_direction(wind) * s_curve_amount;
 }
 
 fn calculate_bop_displacement(
     wind: Wind,
     c_curve_shape: f32,
-    wrapped_time: f32,
+    bop_phase: f32,
     bop_seed: f32,
 ) -> vec3<f32> {
     let bop_phase_offset = bop_seed * 6.28318;
-    let bop_value = sin(wrapped_time * wind.bop_speed + bop_phase_offset);
+    let bop_value = sin(bop_phase + bop_phase_offset);
     let vertical_amount = bop_value * wind.bop_strength * c_curve_shape;
 
     return vec3<f32>(0.0, vertical_amount, 0.0);
@@ -194,7 +389,8 @@ fn calculate_twist(
     c_curve_shape: f32,
     local_pos: vec3<f32>,
 ) -> vec3<f32> {
-    let twist = (macro_noise * 2.0 - 1.0) * wind.twist_strength;
+    // Updrafts and downwash add to the twist on top of the horizontal wind.
+    let twist = (macro_noise * 2.0 - 1.0) * wind.twist_strength * length(calculate_wind_direction(wind));
     let twist_angle = twist * c_curve_shape;
 
     let cos_a = cos(twist_angle);
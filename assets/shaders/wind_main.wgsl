@@ -1,35 +1,36 @@
 #import bevy_pbr::mesh_view_bindings::view
 #import bevy_pbr::mesh_functions::{get_model_matrix, get_world_from_local}
 #import bevy_pbr::view_transformations::position_world_to_clip
-#import bevy_pbr::{
-    pbr_fragment::{pbr_input_from_standard_material, pbr_material_from_standard_material},
-    pbr_functions::{alpha_discard, apply_pbr_lighting, main_pass_post_lighting_processing}
-}
-#import bevy_pbr::forward_io::{Vertex, VertexOutput, FragmentOutput}
+#import bevy_pbr::forward_io::{Vertex, VertexOutput}
 
-#import "shaders/wind.wgsl"::{Wind, BindlessWindIndices}
-#import "shaders/wind_displace.wgsl"::{DisplacedVertex, SampledNoise, InstanceInfo,  displace_vertex_and_calc_normal}
-#import bevy_pbr::mesh_view_bindings::globals
+#import "shaders/wind.wgsl"::{WindMaterialUniform, WindShared, BindlessWindIndices, MAX_WIND_PHASES}
+#import "shaders/wind_displace.wgsl"::{DisplacedVertex, SampledNoise, InstanceInfo, apply_flow_field, apply_wind_zones, calculate_noise_lod, calculate_volume_coord, calculate_phase_coord, calculate_impulse_displacement, calculate_interactor_displacement, calculate_trample_uv, calculate_trample_displacement, displace_vertex_and_calc_normal}
 #import bevy_pbr::mesh_bindings::mesh
 
 #ifdef BINDLESS
-#import bevy_render::bindless::{bindless_samplers_filtering, bindless_textures_2d}
-#import bevy_pbr::pbr_bindings::{material_array, material_indices}
-#else
-#import bevy_pbr::pbr_bindings::material
+#import bevy_render::bindless::{bindless_samplers_filtering, bindless_textures_2d, bindless_textures_3d}
 #endif
 
 #ifdef BINDLESS
 @group(2) @binding(100) var<storage> wind_indices:
     array<BindlessWindIndices>;
 @group(2) @binding(101) var<storage> wind_material:
-    array<Wind>;
+    array<WindMaterialUniform>;
+@group(2) @binding(102) var<storage> wind_shared_buffers:
+    binding_array<WindShared>;
 
 #else
 
-@group(2) @binding(50) var<uniform> wind: Wind;
+@group(2) @binding(50) var<uniform> wind_uniform: WindMaterialUniform;
 @group(2) @binding(51) var noise_texture: texture_2d<f32>;
 @group(2) @binding(52) var noise_texture_sampler: sampler;
+@group(2) @binding(53) var flow_texture: texture_2d<f32>;
+@group(2) @binding(54) var flow_texture_sampler: sampler;
+@group(2) @binding(55) var trample_texture: texture_2d<f32>;
+@group(2) @binding(56) var trample_texture_sampler: sampler;
+@group(2) @binding(57) var noise_volume: texture_3d<f32>;
+@group(2) @binding(58) var noise_volume_sampler: sampler;
+@group(2) @binding(59) var<storage, read> wind_shared: WindShared;
 
 #endif
 
@@ -40,9 +41,16 @@ fn vertex(vertex: Vertex) -> VertexOutput {
 
 #ifdef BINDLESS
     let slot = mesh[vertex.instance_index].material_and_lightmap_bind_group_slot & 0xffffu;
-    let wind =  wind_material[wind_indices[slot].material];
+    let wind_uniform = wind_material[wind_indices[slot].material];
     let noise_texture =   bindless_textures_2d[wind_indices[slot].noise_texture];
     let noise_texture_sampler =  bindless_samplers_filtering[wind_indices[slot].noise_texture_sampler];
+    let flow_texture = bindless_textures_2d[wind_indices[slot].flow_texture];
+    let flow_texture_sampler = bindless_samplers_filtering[wind_indices[slot].flow_texture_sampler];
+    let trample_texture = bindless_textures_2d[wind_indices[slot].trample_texture];
+    let trample_texture_sampler = bindless_samplers_filtering[wind_indices[slot].trample_texture_sampler];
+    let noise_volume = bindless_textures_3d[wind_indices[slot].noise_volume];
+    let noise_volume_sampler = bindless_samplers_filtering[wind_indices[slot].noise_volume_sampler];
+    let wind_shared = wind_shared_buffers[wind_indices[slot].shared_buffer];
 #endif
 
     // --- INSTANCE ---
@@ -50,8 +58,22 @@ fn vertex(vertex: Vertex) -> VertexOutput {
     let camera_world_pos = view.world_position.xyz;
     instance.world_from_local = get_world_from_local(vertex.instance_index);
     instance.instance_position = instance.world_from_local[3];
-    instance.wrapped_time = globals.time % 1000.0;
+    instance.phases = wind_shared.phases[min(wind_uniform.phase_slot, MAX_WIND_PHASES - 1u)];
     instance.instance_index = vertex.instance_index;
+    instance.external_displacement = calculate_impulse_displacement(wind_shared.impulses, wind_shared.impulse_count, instance.instance_position.xyz)
+        + calculate_interactor_displacement(wind_shared.interactors, wind_shared.interactor_count, instance.instance_position.xyz);
+
+    // --- TRAMPLE ---
+    let trample_uv = calculate_trample_uv(wind_shared.trample, instance.instance_position.xyz);
+    if (all(trample_uv >= vec2<f32>(0.0)) && all(trample_uv <= vec2<f32>(1.0))) {
+        let trample_sample = textureSampleLevel(trample_texture, trample_texture_sampler, trample_uv, 0.0);
+        instance.external_displacement += calculate_trample_displacement(wind_shared.trample, trample_sample);
+    }
+
+    // Direction and strength change with gusts, so they come with the phases.
+    var wind = wind_uniform.wind;
+    wind.direction = instance.phases.direction;
+    wind.strength = instance.phases.strength;
 
     // --- TEXTURE SAMPLING ---
     let dist_to_camera = distance(instance.instance_position.xyz, view.world_position.xyz);
@@ -61,24 +83,38 @@ fn vertex(vertex: Vertex) -> VertexOutput {
     noise.micro_noise = 0.0;
     noise.phase_noise = vec2<f32>(0.0);
 
-    let macro_coord = instance.instance_position.xz * wind.noise_scale + instance.wrapped_time * wind.scroll_speed * wind.direction;
-    noise.macro_noise = textureSampleLevel(noise_texture, noise_texture_sampler, macro_coord, 0.0).r;
+    let macro_coord = instance.instance_position.xz * wind.noise_scale + instance.phases.macro_offset;
+    let noise_texture_size = wind_uniform.noise_texture_size;
+    let macro_lod = calculate_noise_lod(dist_to_camera, wind.noise_scale, noise_texture_size);
+    if (wind.enable_noise_volume == 1u) {
+        let volume_coord = calculate_volume_coord(macro_coord, instance.phases);
+        noise.macro_noise = textureSampleLevel(noise_volume, noise_volume_sampler, volume_coord, 0.0).r;
+    } else {
+        noise.macro_noise = textureSampleLevel(noise_texture, noise_texture_sampler, macro_coord, macro_lod).r;
+    }
 
     if (lod_fade > 0.0) {
-        let micro_coord = instance.instance_position.xz * wind.micro_noise_scale + instance.wrapped_time * wind.micro_scroll_speed;
-        noise.micro_noise = textureSampleLevel(noise_texture, noise_texture_sampler, micro_coord, 0.0).r;
+        let micro_coord = instance.instance_position.xz * wind.micro_noise_scale + instance.phases.micro_offset;
+        let micro_lod = calculate_noise_lod(dist_to_camera, wind.micro_noise_scale, noise_texture_size);
+        noise.micro_noise = textureSampleLevel(noise_texture, noise_texture_sampler, micro_coord, micro_lod).g;
 
-        let texture_dimension = 512.0;
-        let phase_coord_x = f32(instance.instance_index % u32(texture_dimension)) / texture_dimension;
-        let phase_coord_y = f32(instance.instance_index / u32(texture_dimension)) / texture_dimension;
-        let phase_coord = vec2<f32>(phase_coord_x, phase_coord_y);
+        let phase_coord = calculate_phase_coord(wind, instance.instance_position.xyz, instance.instance_index, noise_texture_size);
         let phase_sample = textureSampleLevel(noise_texture, noise_texture_sampler, phase_coord, 0.0);
-        noise.phase_noise = vec2(phase_sample.g, phase_sample.b);
+        noise.phase_noise = vec2(phase_sample.b, phase_sample.a);
+    }
+
+    // --- FLOW FIELD ---
+    var flow_wind = wind;
+    if (wind.enable_flow_field == 1u) {
+        let flow_coord = instance.instance_position.xz * wind.flow_scale + instance.phases.flow_offset;
+        let flow_sample = textureSampleLevel(flow_texture, flow_texture_sampler, flow_coord, 0.0).rg;
+        flow_wind = apply_flow_field(wind, flow_sample);
     }
 
     // --- DISPLACEMENT ---
+    let zoned_wind = apply_wind_zones(flow_wind, wind_uniform.zones, wind_uniform.zone_count, instance.instance_position.xyz);
     let displaced = displace_vertex_and_calc_normal(
-        wind,
+        zoned_wind,
         noise,
         vertex.position,
         instance,
@@ -96,18 +132,3 @@ fn vertex(vertex: Vertex) -> VertexOutput {
 
     return out;
 }
-
-@fragment
-fn fragment(
-    in: VertexOutput,
-    @builtin(front_facing) is_front: bool,
-) -> FragmentOutput {
-    var pbr_input = pbr_input_from_standard_material(in, is_front);
-    pbr_input.material.base_color = alpha_discard(pbr_input.material, pbr_input.material.base_color);
-
-    var out: FragmentOutput;
-    out.color = apply_pbr_lighting(pbr_input);
-    out.color = main_pass_post_lighting_processing(pbr_input, out.color);
-
-    return out;
-}
@@ -0,0 +1,62 @@
+#[path = "utils/example.rs"]
+mod example;
+
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+use bevy_feronia::prelude::*;
+use example::*;
+
+fn main() -> AppExit {
+    App::new()
+        .insert_resource(Wind {
+            enable_billboarding: true,
+            enable_edge_correction: true,
+            round_exponent: 80.,
+            ..default()
+        })
+        .add_plugins((ExamplePlugin, UnlitMaterialWindPlugin))
+        .add_systems(Startup, setup)
+        .add_systems(Update, init_grass)
+        .run()
+}
+
+fn setup(mut cmd: Commands, assets: Res<AssetServer>) {
+    cmd.spawn(SceneRoot(assets.load("grass.glb#Scene0")));
+}
+
+/// Swaps the loaded PBR materials for unlit ones, which are then made wind affected.
+///
+/// Each PBR material is converted once, so entities that shared one keep sharing the unlit one.
+fn init_grass(
+    mut cmd: Commands,
+    q: Query<
+        (Entity, &MeshMaterial3d<StandardMaterial>),
+        (With<Mesh3d>, Without<Landscape>, Without<WindAffected>),
+    >,
+    standard_materials: Res<Assets<StandardMaterial>>,
+    mut unlit_materials: ResMut<Assets<UnlitFoliageMaterial>>,
+    mut converted: Local<HashMap<AssetId<StandardMaterial>, Handle<UnlitFoliageMaterial>>>,
+) {
+    for (e, material) in &q {
+        let unlit = match converted.get(&material.id()) {
+            Some(unlit) => unlit.clone(),
+            None => {
+                let Some(standard) = standard_materials.get(material) else {
+                    continue;
+                };
+
+                let unlit = unlit_materials.add(UnlitFoliageMaterial {
+                    base_color: standard.base_color,
+                    base_color_texture: standard.base_color_texture.clone(),
+                    alpha_mode: standard.alpha_mode,
+                });
+                converted.insert(material.id(), unlit.clone());
+                unlit
+            }
+        };
+
+        cmd.entity(e)
+            .remove::<MeshMaterial3d<StandardMaterial>>()
+            .insert((MeshMaterial3d(unlit), WindAffected));
+    }
+}
@@ -0,0 +1,444 @@
+use std::any::TypeId;
+use std::collections::VecDeque;
+use std::f64::consts::TAU;
+
+use bevy::math::DVec2;
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::render::render_resource::ShaderType;
+
+use crate::gust::WindGustNoise;
+use crate::prelude::*;
+
+/// Time driving the wind animation, separate from the renderer's time.
+///
+/// Advanced with the virtual [`Time`] unless `paused`, for lockstep or replays pause it and drive
+/// it with [`WindClock::set`] or [`WindClock::advance`].
+#[derive(Resource, Debug, Clone, PartialEq)]
+pub struct WindClock {
+    pub time_scale: f32,
+    pub paused: bool,
+    elapsed: f64,
+}
+
+impl Default for WindClock {
+    fn default() -> Self {
+        Self {
+            time_scale: 1.0,
+            paused: false,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl WindClock {
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    pub fn set(&mut self, elapsed: f64) {
+        self.elapsed = elapsed;
+    }
+
+    /// Steps the clock manually, e.g. from a fixed timestep or replay while `paused` is set.
+    pub fn advance(&mut self, delta: f64) {
+        self.elapsed += delta * self.time_scale as f64;
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn with_time_scale(mut self, time_scale: f32) -> Self {
+        self.time_scale = time_scale;
+        self
+    }
+}
+
+/// Most distinct phase speeds animated at once, further overrides follow the global wind's
+/// phases.
+pub const MAX_WIND_PHASES: usize = 32;
+
+/// [`WindClock`] seconds between the points at which the phases pick up changed speeds.
+pub const WIND_PHASE_STEP: f64 = 1.0 / 60.0;
+
+/// [`WindClock`] seconds of past speeds kept for [`WindPhases::at`].
+pub const WIND_PHASE_HISTORY: f64 = 2.0;
+
+/// Larger clock jumps, and any jump back past a step, restart the phases instead of catching up.
+const MAX_WIND_PHASE_CATCH_UP: f64 = 1.0;
+
+/// Animation offsets by [`WindClock`] time, uploaded once through the [`WindShared`] buffer.
+///
+/// While the speeds stay the same the offsets are the speeds times the clock time, so the same
+/// time always gives the same offsets. Changed speeds, e.g. from a [`WindTransition`] or gusts,
+/// are picked up every [`WIND_PHASE_STEP`] of clock time from where the offsets are at that point.
+/// That keeps the animation continuous, and as transitions and gusts are functions of the clock
+/// time, independent of the frame rate. Setting the clock back, or far ahead, restarts the
+/// offsets as if the current speeds had been used from the start.
+///
+/// Materials whose [`WindOverride`] changes the speeds, direction or strength get their own slot,
+/// which is freed once no material uses it.
+#[derive(Resource, Debug, Clone)]
+pub struct WindPhases {
+    values: Vec<Option<WindPhaseSlot>>,
+    /// Slots used by the materials of each wind affected material type.
+    used: HashMap<TypeId, HashSet<u32>>,
+    /// Last step the speeds were picked up at, `None` until the first update.
+    step: Option<i64>,
+    /// Global wind of the last update, which new slots start from.
+    wind: Wind,
+    elapsed: f64,
+}
+
+impl Default for WindPhases {
+    fn default() -> Self {
+        let wind = Wind::default();
+        Self {
+            values: vec![Some(WindPhaseSlot::new(
+                WindOverride::default(),
+                &wind,
+                0.0,
+            ))],
+            used: HashMap::default(),
+            step: None,
+            wind,
+            elapsed: 0.0,
+        }
+    }
+}
+
+impl WindPhases {
+    /// [`WindClock`] time the phases were last updated to.
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+
+    /// Phases by slot at the last update, free slots are zeroed.
+    pub fn values(&self) -> impl Iterator<Item = WindPhasesUniform> + '_ {
+        self.values
+            .iter()
+            .map(|value| value.as_ref().map(|slot| slot.uniform).unwrap_or_default())
+    }
+
+    /// Phases at [`WindClock`] time `time` of the slot whose override gives the speeds of `wind`,
+    /// the global ones if there is none.
+    ///
+    /// Exact back to [`WIND_PHASE_HISTORY`] seconds before the last update, later times assume
+    /// the speeds stay the same.
+    pub fn at(&self, wind: &Wind, global: &Wind, time: f64) -> WindPhasesUniform {
+        let key = phase_key(&WindOverride::from(wind.clone()));
+        let slot = self
+            .values
+            .iter()
+            .flatten()
+            .find(|slot| phase_key(&WindOverride::from(slot.key.apply(global))) == key)
+            .or_else(|| self.values.iter().flatten().next());
+
+        slot.map(|slot| slot.offsets_at(time))
+            .unwrap_or_default()
+            .uniform(wind)
+    }
+
+    /// Index of the phases for a material of type `W` with `wind_override`, taking a free slot
+    /// if there is none yet.
+    pub(crate) fn slot<W: Material>(&mut self, wind_override: &WindOverride) -> u32 {
+        let slot = self.find_or_insert(phase_key(wind_override));
+        // Kept until the material shows up in the next `set_used`.
+        self.used.entry(TypeId::of::<W>()).or_default().insert(slot);
+        slot
+    }
+
+    fn find_or_insert(&mut self, key: WindOverride) -> u32 {
+        if let Some(index) = self
+            .values
+            .iter()
+            .position(|value| value.as_ref().is_some_and(|slot| slot.key == key))
+        {
+            return index as u32;
+        }
+
+        let value = Some(WindPhaseSlot::new(key, &self.wind, self.elapsed));
+        if let Some(index) = self.values.iter().position(Option::is_none) {
+            self.values[index] = value;
+            return index as u32;
+        }
+        if self.values.len() >= MAX_WIND_PHASES {
+            warn_once!("More than {MAX_WIND_PHASES} distinct wind speeds, using the global phases");
+            return 0;
+        }
+
+        self.values.push(value);
+        (self.values.len() - 1) as u32
+    }
+
+    /// Replaces the slots used by materials of type `W`, freeing the ones no type uses anymore.
+    pub(crate) fn set_used<W: Material>(&mut self, slots: HashSet<u32>) {
+        self.used.insert(TypeId::of::<W>(), slots);
+
+        for (index, value) in self.values.iter_mut().enumerate().skip(1) {
+            let index = index as u32;
+            if !self.used.values().any(|slots| slots.contains(&index)) {
+                *value = None;
+            }
+        }
+    }
+
+    /// Moves the phases to `elapsed`, picking up the speeds of `wind_at` at every step since the
+    /// last update. `wind` is the global wind at `elapsed`.
+    fn update(&mut self, elapsed: f64, wind: &Wind, wind_at: impl Fn(f64) -> Wind) {
+        let target = (elapsed / WIND_PHASE_STEP).floor() as i64;
+
+        match self.step {
+            Some(step)
+                if step <= target
+                    && (target - step) as f64 * WIND_PHASE_STEP <= MAX_WIND_PHASE_CATCH_UP =>
+            {
+                for step in step + 1..=target {
+                    let time = step as f64 * WIND_PHASE_STEP;
+                    let wind = wind_at(time);
+                    for slot in self.values.iter_mut().flatten() {
+                        slot.step(&wind, time);
+                    }
+                }
+            }
+            _ => {
+                for slot in self.values.iter_mut().flatten() {
+                    *slot = WindPhaseSlot::new(slot.key.clone(), wind, elapsed);
+                }
+            }
+        }
+
+        for slot in self.values.iter_mut().flatten() {
+            slot.uniform = slot.offsets_at(elapsed).uniform(&slot.key.apply(wind));
+        }
+
+        self.step = Some(target);
+        self.wind = wind.clone();
+        self.elapsed = elapsed;
+    }
+}
+
+/// The fields of `wind_override` the phases depend on, so overrides only differing in others
+/// share a slot.
+fn phase_key(wind_override: &WindOverride) -> WindOverride {
+    WindOverride {
+        direction: wind_override.direction,
+        strength: wind_override.strength,
+        scroll_speed: wind_override.scroll_speed,
+        micro_scroll_speed: wind_override.micro_scroll_speed,
+        s_curve_speed: wind_override.s_curve_speed,
+        bop_speed: wind_override.bop_speed,
+        flow_scroll_speed: wind_override.flow_scroll_speed,
+        volume_speed: wind_override.volume_speed,
+        ..default()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct WindPhaseSlot {
+    key: WindOverride,
+    /// Oldest first, the first one starts at or before [`WIND_PHASE_HISTORY`] seconds ago.
+    anchors: VecDeque<WindPhaseAnchor>,
+    uniform: WindPhasesUniform,
+}
+
+impl WindPhaseSlot {
+    /// Starts from zero at time zero, as if the current speeds had always been used.
+    fn new(key: WindOverride, wind: &Wind, elapsed: f64) -> Self {
+        let wind = key.apply(wind);
+        let anchor = WindPhaseAnchor {
+            speeds: WindPhaseSpeeds::from(&wind),
+            ..default()
+        };
+
+        Self {
+            key,
+            anchors: VecDeque::from([anchor]),
+            uniform: anchor.offsets_at(elapsed).uniform(&wind),
+        }
+    }
+
+    fn offsets_at(&self, time: f64) -> WindPhaseOffsets {
+        self.anchors
+            .iter()
+            .rev()
+            .find(|anchor| anchor.time <= time)
+            .or(self.anchors.front())
+            .map(|anchor| anchor.offsets_at(time))
+            .unwrap_or_default()
+    }
+
+    /// Continues at the speeds of `global` from `time` on.
+    fn step(&mut self, global: &Wind, time: f64) {
+        let speeds = WindPhaseSpeeds::from(&self.key.apply(global));
+        let Some(last) = self.anchors.back().copied() else {
+            return;
+        };
+        if last.speeds == speeds {
+            return;
+        }
+
+        self.anchors.push_back(WindPhaseAnchor {
+            time,
+            offsets: last.offsets_at(time),
+            speeds,
+        });
+        while self
+            .anchors
+            .get(1)
+            .is_some_and(|anchor| anchor.time <= time - WIND_PHASE_HISTORY)
+        {
+            self.anchors.pop_front();
+        }
+    }
+}
+
+/// Offsets at `time`, moving at `speeds` from there on.
+#[derive(Debug, Clone, Copy, Default)]
+struct WindPhaseAnchor {
+    time: f64,
+    offsets: WindPhaseOffsets,
+    speeds: WindPhaseSpeeds,
+}
+
+impl WindPhaseAnchor {
+    fn offsets_at(&self, time: f64) -> WindPhaseOffsets {
+        let delta = time - self.time;
+        // Texture offsets wrap after two periods, which is seamless for both repeat and mirror
+        // repeat addressing. Sine phases wrap after a full turn, the volume loops every 1.0.
+        let offset = |value: f64, speed: f64| (value + delta * speed).rem_euclid(2.0);
+        let phase = |value: f64, speed: f64| (value + delta * speed).rem_euclid(TAU);
+        let offsets = &self.offsets;
+        let speeds = &self.speeds;
+
+        WindPhaseOffsets {
+            macro_offset: DVec2::new(
+                offset(offsets.macro_offset.x, speeds.macro_velocity.x),
+                offset(offsets.macro_offset.y, speeds.macro_velocity.y),
+            ),
+            flow_offset: DVec2::new(
+                offset(offsets.flow_offset.x, speeds.flow_velocity.x),
+                offset(offsets.flow_offset.y, speeds.flow_velocity.y),
+            ),
+            micro_offset: offset(offsets.micro_offset, speeds.micro),
+            s_curve_phase: phase(offsets.s_curve_phase, speeds.s_curve),
+            bop_phase: phase(offsets.bop_phase, speeds.bop),
+            volume_offset: (offsets.volume_offset + delta * speeds.volume).rem_euclid(1.0),
+        }
+    }
+}
+
+/// Kept in double precision, only the uploaded values are rounded.
+#[derive(Debug, Clone, Copy, Default)]
+struct WindPhaseOffsets {
+    macro_offset: DVec2,
+    flow_offset: DVec2,
+    micro_offset: f64,
+    s_curve_phase: f64,
+    bop_phase: f64,
+    volume_offset: f64,
+}
+
+impl WindPhaseOffsets {
+    fn uniform(&self, wind: &Wind) -> WindPhasesUniform {
+        WindPhasesUniform {
+            macro_offset: self.macro_offset.as_vec2(),
+            flow_offset: self.flow_offset.as_vec2(),
+            micro_offset: self.micro_offset as f32,
+            s_curve_phase: self.s_curve_phase as f32,
+            bop_phase: self.bop_phase as f32,
+            volume_offset: self.volume_offset as f32,
+            direction: wind.direction,
+            strength: wind.strength,
+        }
+    }
+}
+
+/// The fields of [`Wind`] the offsets move with.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct WindPhaseSpeeds {
+    macro_velocity: DVec2,
+    flow_velocity: DVec2,
+    micro: f64,
+    s_curve: f64,
+    bop: f64,
+    volume: f64,
+}
+
+impl From<&Wind> for WindPhaseSpeeds {
+    fn from(wind: &Wind) -> Self {
+        let direction = wind.direction.as_dvec2();
+
+        Self {
+            macro_velocity: direction * wind.scroll_speed as f64,
+            flow_velocity: direction * wind.flow_scroll_speed as f64,
+            micro: wind.micro_scroll_speed as f64,
+            s_curve: wind.s_curve_speed as f64,
+            bop: wind.bop_speed as f64,
+            volume: wind.volume_speed as f64,
+        }
+    }
+}
+
+/// Animation offsets, wrapped on the CPU so the shaders never see a jump.
+#[derive(ShaderType, Reflect, Default, Clone, Copy, PartialEq, Debug)]
+pub struct WindPhasesUniform {
+    pub macro_offset: Vec2,
+    pub flow_offset: Vec2,
+    pub micro_offset: f32,
+    pub s_curve_phase: f32,
+    pub bop_phase: f32,
+    /// Depth into the [`WindNoiseVolume`], which loops every 1.0.
+    pub volume_offset: f32,
+    /// Current direction and strength, which gusts change every frame.
+    pub direction: Vec2,
+    pub strength: f32,
+}
+
+pub(crate) fn update_wind_clock(time: Res<Time>, mut clock: ResMut<WindClock>) {
+    if clock.paused {
+        return;
+    }
+
+    clock.advance(time.delta_secs_f64());
+}
+
+/// Runs after everything that changes the [`Wind`], including gusts, has run for the frame.
+///
+/// Transitions and gusts are sampled at each step, so the phases don't depend on which frames
+/// the steps fall into.
+pub(crate) fn update_wind_phases(
+    clock: Res<WindClock>,
+    wind: Res<Wind>,
+    transition: Option<Res<WindTransition>>,
+    gust: Option<Res<WindGust>>,
+    gust_noise: Option<Res<WindGustNoise>>,
+    mut phases: ResMut<WindPhases>,
+) {
+    let wind_at = |time: f64| {
+        let blended = transition
+            .as_ref()
+            .and_then(|transition| transition.sample(time));
+        let mut wind_at = blended.clone().unwrap_or_else(|| wind.clone());
+
+        if let (Some(gust), Some(gust_noise)) = (gust.as_ref(), gust_noise.as_ref()) {
+            let mut gust = WindGust::clone(gust);
+            if let Some(blended) = blended {
+                gust.rebase(Some(blended.strength), Some(blended.direction));
+            }
+
+            let sample = gust.sample(&gust_noise.noise, time);
+            wind_at.strength = sample.strength;
+            wind_at.direction = sample.direction;
+        }
+
+        wind_at
+    };
+
+    phases.update(clock.elapsed(), &wind, wind_at);
+}
@@ -1,7 +1,10 @@
 use bevy::{
     pbr::{ExtendedMaterial, MaterialExtension},
     prelude::*,
-    render::render_resource::{AsBindGroup, ShaderRef},
+    render::{
+        render_resource::{AsBindGroup, ShaderRef},
+        storage::ShaderStorageBuffer,
+    },
 };
 
 use crate::{WindPlugin, prelude::*};
@@ -17,9 +20,28 @@ impl Plugin for ExtendedMaterialWindPlugin {
 
 pub type WindAffectedExtendedMaterial = ExtendedMaterial<StandardMaterial, WindAffectedExtension>;
 
+/// Connects a base material `M` to the wind affected material `R` that replaces it.
+///
+/// Only the conversion and access to the [`WindAffectedExtension`] have to be implemented, the
+/// update functions work on any material that carries the extension.
 pub trait WindAffectable<M: Material, R: Material> {
-    fn create_material(base: M, wind: Wind, noise_texture: Handle<Image>) -> R;
-    fn update_material(materials: ResMut<Assets<R>>, wind: Wind);
+    fn create_material(base: M, extension: WindAffectedExtension) -> R;
+    fn extension(material: &R) -> &WindAffectedExtension;
+    fn extension_mut(material: &mut R) -> &mut WindAffectedExtension;
+
+    fn update_material(mut materials: ResMut<Assets<R>>, wind: Wind) {
+        for (_, material) in materials.iter_mut() {
+            let ext = Self::extension_mut(material);
+            ext.wind = ext.wind_override.apply(&wind);
+        }
+    }
+
+    fn update_zones(mut materials: ResMut<Assets<R>>, zones: WindZones) {
+        for (_, material) in materials.iter_mut() {
+            let ext = Self::extension_mut(material);
+            ext.zones = zones.values.clone();
+        }
+    }
 }
 
 impl WindAffectable<StandardMaterial, WindAffectedExtendedMaterial>
@@ -27,44 +49,61 @@ impl WindAffectable<StandardMaterial, WindAffectedExtendedMaterial>
 {
     fn create_material(
         mut base: StandardMaterial,
-        wind: Wind,
-        noise_texture: Handle<Image>,
+        extension: WindAffectedExtension,
     ) -> WindAffectedExtendedMaterial {
         // TODO do in base
         base.double_sided = true;
         base.cull_mode = None;
-        ExtendedMaterial {
-            base,
-            extension: WindAffectedExtension {
-                noise_texture,
-                wind,
-            },
-        }
+        ExtendedMaterial { base, extension }
     }
 
-    fn update_material(mut materials: ResMut<Assets<WindAffectedExtendedMaterial>>, wind: Wind) {
-        for (_, material) in materials.iter_mut() {
-            let ext = &mut material.extension;
-            ext.wind = wind.clone();
-        }
+    fn extension(material: &WindAffectedExtendedMaterial) -> &WindAffectedExtension {
+        &material.extension
+    }
+
+    fn extension_mut(material: &mut WindAffectedExtendedMaterial) -> &mut WindAffectedExtension {
+        &mut material.extension
     }
 }
 
 #[derive(Asset, Reflect, AsBindGroup, Debug, Clone)]
-#[data(50, WindUniform, binding_array(101))]
-#[bindless(index_table(range(50..53), binding(100)))]
+#[data(50, WindMaterialUniform, binding_array(101))]
+#[bindless(index_table(range(50..60), binding(100)))]
 pub struct WindAffectedExtension {
     pub wind: Wind,
+    pub wind_override: WindOverride,
+    pub zones: Vec<WindZoneUniform>,
+    /// Index into the [`WindPhases`] for `wind_override`.
+    pub phase_slot: u32,
+    /// Size of `noise_texture` in texels, kept in sync with the image by the plugin.
+    pub noise_texture_size: Vec2,
 
     #[texture(51)]
     #[sampler(52)]
     pub noise_texture: Handle<Image>,
+
+    #[texture(53)]
+    #[sampler(54)]
+    pub flow_texture: Handle<Image>,
+
+    #[texture(55)]
+    #[sampler(56)]
+    pub trample_texture: Handle<Image>,
+
+    #[texture(57, dimension = "3d")]
+    #[sampler(58)]
+    pub noise_volume: Handle<Image>,
+
+    /// The [`WindShared`] buffer, the same for every material.
+    #[storage(59, read_only, binding_array(102))]
+    pub shared_buffer: Handle<ShaderStorageBuffer>,
 }
 
 impl From<&Wind> for WindUniform {
     fn from(wind: &Wind) -> Self {
         WindUniform {
             direction: wind.direction,
+            vertical: wind.vertical,
             strength: wind.strength,
             noise_scale: wind.noise_scale,
             scroll_speed: wind.scroll_speed,
@@ -89,24 +128,47 @@ impl From<&Wind> for WindUniform {
             },
             edge_correction_factor: wind.edge_correction_factor,
             lod_threshold: wind.lod_threshold,
+            enable_flow_field: match wind.enable_flow_field {
+                true => 1,
+                _ => 0,
+            },
+            flow_scale: wind.flow_scale,
+            flow_scroll_speed: wind.flow_scroll_speed,
+            enable_noise_volume: match wind.enable_noise_volume {
+                true => 1,
+                _ => 0,
+            },
+            volume_speed: wind.volume_speed,
+            phase_seed: match wind.phase_seed {
+                WindPhaseSeed::Position => 0,
+                WindPhaseSeed::InstanceIndex => 1,
+            },
         }
     }
 }
 
-impl<'a> From<&'a WindAffectedExtension> for WindUniform {
+impl<'a> From<&'a WindAffectedExtension> for WindMaterialUniform {
     fn from(material_extension: &'a WindAffectedExtension) -> Self {
-        WindUniform::from(&material_extension.wind)
+        let mut zones = [WindZoneUniform::default(); MAX_WIND_ZONES];
+        let zone_count = material_extension.zones.len().min(MAX_WIND_ZONES);
+        zones[..zone_count].copy_from_slice(&material_extension.zones[..zone_count]);
+
+        WindMaterialUniform {
+            wind: WindUniform::from(&material_extension.wind),
+            zones,
+            noise_texture_size: material_extension.noise_texture_size,
+            zone_count: zone_count as u32,
+            phase_slot: material_extension.phase_slot,
+        }
     }
 }
 
 const SHADER_MAIN_ASSET_PATH: &str = "shaders/wind_main.wgsl";
 const SHADER_PREPASS_ASSET_PATH: &str = "shaders/wind_prepass.wgsl";
 
+// The fragment shaders are left to the base material, the vertex shaders output the same
+// `VertexOutput` as the bevy_pbr ones.
 impl MaterialExtension for WindAffectedExtension {
-    fn fragment_shader() -> ShaderRef {
-        SHADER_MAIN_ASSET_PATH.into()
-    }
-
     fn vertex_shader() -> ShaderRef {
         SHADER_MAIN_ASSET_PATH.into()
     }
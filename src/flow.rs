@@ -0,0 +1,124 @@
+use bevy::image::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use noise::{NoiseFn, Perlin};
+
+use crate::prelude::*;
+use crate::texture::torus_point;
+
+/// Direction texture used when `Wind::enable_flow_field` is set, the red and green channels hold
+/// the xz direction remapped to 0..1.
+///
+/// Generated from tileable curl noise in the background at startup, and again when the
+/// [`WindSeed`] changes, unless inserted beforehand, e.g. from an image loaded with `is_srgb`
+/// disabled. A coarse version of the field stands in until the first one is done.
+#[derive(Resource)]
+pub struct WindFlowTexture(pub Handle<Image>);
+
+/// Seed of the last generation started, absent when the flow texture was inserted by the user.
+#[derive(Resource)]
+pub(crate) struct GeneratedWindFlow(Option<WindSeed>);
+
+const FLOW_TEXTURE_SIZE: u32 = 256;
+/// A flat placeholder would point everything along one axis, unlike a coarse field.
+const FLOW_PLACEHOLDER_SIZE: u32 = 8;
+
+pub(crate) fn setup_wind_flow_texture(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    flow_texture: Option<Res<WindFlowTexture>>,
+    seed: Res<WindSeed>,
+) {
+    if flow_texture.is_some() {
+        return;
+    }
+
+    let handle = images.add(generate_flow_image(*seed, FLOW_PLACEHOLDER_SIZE));
+
+    commands.insert_resource(WindFlowTexture(handle));
+    commands.insert_resource(GeneratedWindFlow(None));
+}
+
+pub(crate) fn regenerate_wind_flow_texture(
+    mut images: ResMut<Assets<Image>>,
+    flow_texture: Res<WindFlowTexture>,
+    generated: Option<ResMut<GeneratedWindFlow>>,
+    seed: Res<WindSeed>,
+    mut generation: Local<Option<Task<Image>>>,
+) {
+    let Some(mut generated) = generated else {
+        return;
+    };
+
+    if let Some(image) = generation
+        .as_mut()
+        .and_then(|task| block_on(poll_once(task)))
+    {
+        *generation = None;
+        images.insert(&flow_texture.0, image);
+    }
+
+    if generated.0 == Some(*seed) {
+        return;
+    }
+
+    // Replacing the task drops, and with that cancels, a generation that is still running.
+    let seed = *seed;
+    *generation = Some(
+        AsyncComputeTaskPool::get()
+            .spawn(async move { generate_flow_image(seed, FLOW_TEXTURE_SIZE) }),
+    );
+    generated.0 = Some(seed);
+}
+
+fn generate_flow_image(seed: WindSeed, texture_size: u32) -> Image {
+    let frequency = 4.0;
+    let epsilon = 0.5 / texture_size as f64;
+    let mut image_buffer = Vec::with_capacity((texture_size * texture_size * 2) as usize);
+
+    // Offset so the flow doesn't line up with the noise texture.
+    let perlin = Perlin::new(seed.0.wrapping_add(1));
+    // Sampled on a torus so the field tiles with the repeating sampler.
+    let potential = |u: f64, v: f64| perlin.get(torus_point(u, v, frequency));
+
+    for y in 0..texture_size {
+        for x in 0..texture_size {
+            let u = x as f64 / texture_size as f64;
+            let v = y as f64 / texture_size as f64;
+
+            // Curl of the noise potential, which gives a divergence free (swirling) field.
+            let d_du = (potential(u + epsilon, v) - potential(u - epsilon, v)) / (2.0 * epsilon);
+            let d_dv = (potential(u, v + epsilon) - potential(u, v - epsilon)) / (2.0 * epsilon);
+
+            let flow = Vec2::new(d_dv as f32, -d_du as f32).normalize_or(Vec2::X);
+
+            image_buffer.push(((flow.x * 0.5 + 0.5) * 255.0) as u8);
+            image_buffer.push(((flow.y * 0.5 + 0.5) * 255.0) as u8);
+        }
+    }
+
+    let mut flow_image = Image::new(
+        Extent3d {
+            width: texture_size,
+            height: texture_size,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        image_buffer,
+        TextureFormat::Rg8Unorm,
+        default(),
+    );
+
+    flow_image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        label: Some("Wind Flow Sampler".into()),
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        address_mode_w: ImageAddressMode::Repeat,
+        mag_filter: ImageFilterMode::Linear,
+        min_filter: ImageFilterMode::Linear,
+        ..default()
+    });
+
+    flow_image
+}
@@ -0,0 +1,184 @@
+use bevy::prelude::*;
+use noise::{Fbm, MultiFractal, NoiseFn, Perlin};
+
+use crate::prelude::*;
+use crate::{clock, preset, transition};
+
+pub struct WindGustPlugin;
+
+impl Plugin for WindGustPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<WindGust>()
+            .init_resource::<WindGustState>()
+            .init_resource::<WindGustNoise>()
+            .add_systems(Startup, setup_wind_gust)
+            .add_systems(
+                Update,
+                update_wind_gust
+                    .after(transition::update_wind_transition)
+                    .after(preset::apply_wind_preset)
+                    .after(clock::update_wind_clock),
+            );
+    }
+}
+
+/// Gusts and veers on top of [`Wind`], written into its `strength` and `direction` every frame.
+///
+/// Other changes to those fields, e.g. from a [`WindTransition`] or a preset, become the new
+/// `base_strength` and `base_direction`. Gusts are a function of the [`WindClock`] time, `seed`
+/// follows the [`WindSeed`] when that changes.
+#[derive(Resource, Debug, Clone, Reflect)]
+#[reflect(Resource)]
+pub struct WindGust {
+    pub seed: u32,
+    pub base_strength: f32,
+    /// Relative deviation from `base_strength` at the peak of a gust.
+    pub gustiness: f32,
+    pub min_strength: f32,
+    pub max_strength: f32,
+    pub gust_frequency: f32,
+    pub base_direction: Vec2,
+    /// Maximum veer around `base_direction` in radians.
+    pub max_veer: f32,
+    pub veer_frequency: f32,
+}
+
+impl From<&Wind> for WindGust {
+    fn from(wind: &Wind) -> Self {
+        Self {
+            seed: 0,
+            base_strength: wind.strength,
+            gustiness: 0.6,
+            min_strength: wind.strength * 0.25,
+            max_strength: wind.strength * 2.0,
+            gust_frequency: 0.2,
+            base_direction: wind.direction,
+            max_veer: 0.5,
+            veer_frequency: 0.05,
+        }
+    }
+}
+
+impl Default for WindGust {
+    fn default() -> Self {
+        Self::from(&Wind::default())
+    }
+}
+
+/// The gusted values written into [`Wind`] this frame.
+#[derive(Resource, Debug, Default, Clone, PartialEq)]
+pub struct WindGustState {
+    pub strength: f32,
+    pub direction: Vec2,
+}
+
+/// Noise for the current `WindGust::seed`, shared with the phases which sample gusts between
+/// frames.
+#[derive(Resource)]
+pub(crate) struct WindGustNoise {
+    seed: u32,
+    pub(crate) noise: Fbm<Perlin>,
+}
+
+impl Default for WindGustNoise {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl WindGustNoise {
+    fn new(seed: u32) -> Self {
+        Self {
+            seed,
+            noise: Fbm::<Perlin>::new(seed).set_octaves(3),
+        }
+    }
+}
+
+impl WindGust {
+    /// Moves `base_strength` and `base_direction` to values set by someone else, the strength
+    /// limits scale along so they don't cap the new strength.
+    pub fn rebase(&mut self, strength: Option<f32>, direction: Option<Vec2>) {
+        if let Some(strength) = strength {
+            if self.base_strength > 0.0 {
+                let scale = strength / self.base_strength;
+                self.min_strength *= scale;
+                self.max_strength *= scale;
+            }
+            self.base_strength = strength;
+        }
+        if let Some(direction) = direction {
+            self.base_direction = direction;
+        }
+    }
+
+    pub fn sample(&self, noise: &Fbm<Perlin>, time: f64) -> WindGustState {
+        let gust = noise.get([time * self.gust_frequency as f64, 0.0]) as f32;
+        let veer = noise.get([time * self.veer_frequency as f64, 100.0]) as f32;
+
+        let strength = (self.base_strength * (1.0 + gust * self.gustiness))
+            .clamp(self.min_strength, self.max_strength.max(self.min_strength));
+        let direction = Vec2::from_angle(veer.clamp(-1.0, 1.0) * self.max_veer)
+            .rotate(self.base_direction.normalize_or(Vec2::X));
+
+        WindGustState {
+            strength,
+            direction,
+        }
+    }
+}
+
+fn setup_wind_gust(
+    mut cmd: Commands,
+    gust: Option<Res<WindGust>>,
+    wind: Res<Wind>,
+    seed: Res<WindSeed>,
+) {
+    if gust.is_none() {
+        cmd.insert_resource(WindGust {
+            seed: seed.0,
+            ..WindGust::from(&*wind)
+        });
+    }
+}
+
+/// Runs after everything else writing the [`Wind`], so its changes are picked up the same frame.
+pub(crate) fn update_wind_gust(
+    clock: Res<WindClock>,
+    gust: Option<ResMut<WindGust>>,
+    mut wind: ResMut<Wind>,
+    mut state: ResMut<WindGustState>,
+    mut noise: ResMut<WindGustNoise>,
+    seed: Res<WindSeed>,
+) {
+    let Some(mut gust) = gust else {
+        return;
+    };
+
+    // The initial seed is copied by `setup_wind_gust`, or chosen with the inserted gust.
+    if seed.is_changed() && !seed.is_added() {
+        gust.seed = seed.0;
+    }
+
+    // Values differing from the last gust were written by someone else.
+    if wind.is_changed() {
+        let strength = (wind.strength != state.strength).then_some(wind.strength);
+        let direction = (wind.direction != state.direction).then_some(wind.direction);
+        if strength.is_some() || direction.is_some() {
+            gust.rebase(strength, direction);
+        }
+    }
+
+    if noise.seed != gust.seed {
+        *noise = WindGustNoise::new(gust.seed);
+    }
+
+    let sample = gust.sample(&noise.noise, clock.elapsed());
+
+    // Bypass change detection so gusts don't trigger `update_materials` every frame.
+    let wind = wind.bypass_change_detection();
+    wind.strength = sample.strength;
+    wind.direction = sample.direction;
+
+    state.set_if_neq(sample);
+}
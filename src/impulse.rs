@@ -0,0 +1,172 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use bevy::render::render_resource::ShaderType;
+
+use crate::prelude::*;
+
+pub const MAX_WIND_IMPULSES: usize = 8;
+
+/// Pushes foliage away from `origin` with a ring expanding at `speed` up to `radius`.
+#[derive(Event, Debug, Clone, Copy, PartialEq)]
+pub struct WindImpulse {
+    pub origin: Vec3,
+    pub radius: f32,
+    pub strength: f32,
+    pub speed: f32,
+    /// Exponential fade per second.
+    pub decay: f32,
+}
+
+/// Active impulses with the [`WindClock`] time they were received at, oldest first.
+#[derive(Resource, Default, Clone, PartialEq, Debug)]
+pub struct WindImpulses {
+    pub values: VecDeque<(f64, WindImpulse)>,
+}
+
+#[derive(ShaderType, Default, Clone, Copy, PartialEq, Debug, Reflect)]
+pub struct WindImpulseUniform {
+    pub origin: Vec3,
+    pub age: f32,
+    pub radius: f32,
+    pub strength: f32,
+    pub speed: f32,
+    pub decay: f32,
+}
+
+impl WindImpulseUniform {
+    pub fn new(impulse: &WindImpulse, age: f32) -> Self {
+        Self {
+            origin: impulse.origin,
+            age,
+            radius: impulse.radius,
+            strength: impulse.strength,
+            speed: impulse.speed,
+            decay: impulse.decay,
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.speed * self.age > self.radius * 1.5 || (-self.decay * self.age).exp() < 0.01
+    }
+
+    /// Matches `calculate_impulse_displacement` in `wind_displace.wgsl`.
+    pub fn displacement(&self, position: Vec3) -> Vec3 {
+        let offset = position.xz() - self.origin.xz();
+        let distance = offset.length();
+        if distance > self.radius || distance < 0.0001 {
+            return Vec3::ZERO;
+        }
+
+        let front = self.speed * self.age;
+        let ring_width = (self.radius * 0.15).max(0.0001);
+        let ring = (-(distance - front).powi(2) / (2.0 * ring_width * ring_width)).exp();
+        let edge_fade = 1.0 - ((distance / self.radius - 0.75) / 0.25).clamp(0.0, 1.0);
+        let fade = (-self.decay * self.age).exp() * edge_fade;
+
+        let direction = offset / distance;
+        Vec3::new(direction.x, 0.0, direction.y) * self.strength * ring * fade
+    }
+}
+
+impl WindImpulses {
+    /// The impulses that have started by [`WindClock`] time `time`, aged accordingly.
+    pub fn uniforms(&self, time: f64) -> impl Iterator<Item = WindImpulseUniform> + '_ {
+        self.values
+            .iter()
+            .filter(move |(start, _)| *start <= time)
+            .map(move |(start, impulse)| WindImpulseUniform::new(impulse, (time - start) as f32))
+    }
+
+    pub fn displacement(&self, position: Vec3, time: f64) -> Vec3 {
+        self.uniforms(time)
+            .map(|impulse| impulse.displacement(position))
+            .sum()
+    }
+}
+
+pub(crate) fn update_wind_impulses(
+    clock: Res<WindClock>,
+    mut events: EventReader<WindImpulse>,
+    mut impulses: ResMut<WindImpulses>,
+) {
+    let time = clock.elapsed();
+    let mut values = impulses.values.clone();
+
+    values.retain(|(start, impulse)| {
+        !WindImpulseUniform::new(impulse, (time - start) as f32).is_finished()
+    });
+
+    for event in events.read() {
+        // Evict the oldest impulse once the GPU array is full.
+        if values.len() >= MAX_WIND_IMPULSES {
+            values.pop_front();
+        }
+        values.push_back((time, *event));
+    }
+
+    impulses.set_if_neq(WindImpulses { values });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn impulses(start: f64) -> WindImpulses {
+        WindImpulses {
+            values: VecDeque::from([(
+                start,
+                WindImpulse {
+                    origin: Vec3::ZERO,
+                    radius: 10.0,
+                    strength: 1.0,
+                    speed: 4.0,
+                    decay: 0.0,
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn ring_pushes_away_from_origin() {
+        let impulses = impulses(1.0);
+
+        assert_eq!(
+            impulses.displacement(Vec3::new(4.0, 0.0, 0.0), 2.0),
+            Vec3::X
+        );
+        assert_eq!(
+            impulses.displacement(Vec3::new(0.0, 5.0, -4.0), 2.0),
+            Vec3::NEG_Z
+        );
+        assert!(
+            impulses
+                .displacement(Vec3::new(1.0, 0.0, 0.0), 3.0)
+                .length()
+                < 0.01
+        );
+    }
+
+    #[test]
+    fn nothing_before_start_or_outside_radius() {
+        let impulses = impulses(1.0);
+
+        assert_eq!(
+            impulses.displacement(Vec3::new(4.0, 0.0, 0.0), 0.5),
+            Vec3::ZERO
+        );
+        assert_eq!(
+            impulses.displacement(Vec3::new(11.0, 0.0, 0.0), 3.5),
+            Vec3::ZERO
+        );
+        assert_eq!(impulses.displacement(Vec3::ZERO, 1.0), Vec3::ZERO);
+    }
+
+    #[test]
+    fn finishes_once_past_radius() {
+        let impulses = impulses(0.0);
+
+        assert!(!impulses.uniforms(1.0).all(|impulse| impulse.is_finished()));
+        assert!(impulses.uniforms(4.0).all(|impulse| impulse.is_finished()));
+    }
+}
@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::ShaderType;
+
+use crate::zone::smoothstep;
+
+pub const MAX_FOLIAGE_INTERACTORS: usize = 16;
+
+const MIN_INTERACTOR_EXTENT: f32 = 0.0001;
+
+/// Bends foliage away from the entity, e.g. a player or vehicle moving through grass.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+#[require(Transform)]
+pub struct FoliageInteractor {
+    pub radius: f32,
+    pub strength: f32,
+    /// Vertical reach, foliage rooted further above or below the entity is unaffected.
+    pub height: f32,
+    /// Amount (0..1) stamped into the [`TrampleMap`](crate::prelude::TrampleMap) while moving, 0 leaves no tracks.
+    pub trample: f32,
+}
+
+impl Default for FoliageInteractor {
+    fn default() -> Self {
+        Self {
+            radius: 1.0,
+            strength: 1.0,
+            height: 2.0,
+            trample: 0.0,
+        }
+    }
+}
+
+/// Interactors closest to the camera, uploaded through the
+/// [`WindShared`](crate::prelude::WindShared) buffer.
+#[derive(Resource, Default, Clone, PartialEq, Debug)]
+pub struct FoliageInteractors {
+    pub values: Vec<FoliageInteractorUniform>,
+}
+
+#[derive(ShaderType, Reflect, Default, Clone, Copy, PartialEq, Debug)]
+pub struct FoliageInteractorUniform {
+    pub position: Vec3,
+    pub radius: f32,
+    pub strength: f32,
+    pub height: f32,
+}
+
+impl FoliageInteractorUniform {
+    pub fn new(interactor: &FoliageInteractor, transform: &GlobalTransform) -> Self {
+        Self {
+            position: transform.translation(),
+            // Kept above zero, the falloff in the shader is undefined for an empty range.
+            radius: interactor.radius.max(MIN_INTERACTOR_EXTENT),
+            strength: interactor.strength,
+            height: interactor.height.max(MIN_INTERACTOR_EXTENT),
+        }
+    }
+
+    /// Matches `calculate_interactor_displacement` in `wind_displace.wgsl`.
+    pub fn displacement(&self, position: Vec3) -> Vec3 {
+        let offset = position.xz() - self.position.xz();
+        let distance = offset.length();
+        if distance > self.radius {
+            return Vec3::ZERO;
+        }
+
+        let radial = 1.0 - smoothstep(0.0, self.radius, distance);
+        let vertical = 1.0 - smoothstep(0.0, self.height, (position.y - self.position.y).abs());
+        let weight = radial * vertical * self.strength;
+
+        let direction = if distance > 0.0001 {
+            offset / distance
+        } else {
+            Vec2::ZERO
+        };
+
+        // Pushed outwards and pressed down, blades right under the interactor only flatten.
+        Vec3::new(direction.x, -0.5, direction.y) * weight
+    }
+}
+
+impl FoliageInteractors {
+    pub fn displacement(&self, position: Vec3) -> Vec3 {
+        self.values
+            .iter()
+            .map(|interactor| interactor.displacement(position))
+            .sum()
+    }
+}
+
+pub(crate) fn collect_foliage_interactors(
+    mut interactors: ResMut<FoliageInteractors>,
+    q: Query<(&FoliageInteractor, &GlobalTransform)>,
+    camera: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let origin = camera
+        .iter()
+        .next()
+        .map(GlobalTransform::translation)
+        .unwrap_or_default();
+
+    let mut values = q.iter().collect::<Vec<_>>();
+    values.sort_by(|(_, a), (_, b)| {
+        a.translation()
+            .distance_squared(origin)
+            .total_cmp(&b.translation().distance_squared(origin))
+    });
+
+    interactors.set_if_neq(FoliageInteractors {
+        values: values
+            .into_iter()
+            .take(MAX_FOLIAGE_INTERACTORS)
+            .map(|(interactor, transform)| FoliageInteractorUniform::new(interactor, transform))
+            .collect(),
+    });
+}
@@ -1,12 +1,30 @@
 use std::marker::PhantomData;
 
-use bevy::image::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::asset::LoadState;
+use bevy::ecs::entity::EntityHashSet;
+use bevy::platform::collections::{HashMap, HashSet};
 use bevy::prelude::*;
-use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
-use noise::{NoiseFn, Perlin};
+use bevy::render::extract_resource::ExtractResourcePlugin;
+use bevy::render::{Render, RenderApp, RenderSet};
+use bevy::transform::TransformSystem;
 
+mod clock;
 mod extension;
+mod flow;
+mod gust;
+mod impulse;
+mod interactor;
 pub mod prelude;
+mod preset;
+mod sampler;
+mod shared;
+mod texture;
+mod trample;
+mod transition;
+mod unlit;
+mod volume;
+mod wind_override;
+mod zone;
 use prelude::*;
 
 pub struct WindPlugin<M: Material, W: WindAffectable<M, W> + Material> {
@@ -22,128 +40,639 @@ impl<M: Material, W: WindAffectable<M, W> + Material> Default for WindPlugin<M,
 }
 
 impl<M: Material, W: WindAffectable<M, W> + Material> Plugin for WindPlugin<M, W> {
+    fn build(&self, app: &mut App) {
+        // Shared between the plugins of all material types.
+        if !app.is_plugin_added::<WindCorePlugin>() {
+            app.add_plugins(WindCorePlugin);
+        }
+
+        app.init_resource::<WindAffectedTypes<W>>()
+            .init_resource::<WindMaterialCache<M, W>>()
+            .add_event::<WindPrototypeAdded<W>>()
+            .add_event::<WindPrototypeRemoved<W>>()
+            .add_systems(
+                Update,
+                (
+                    (
+                        prune_wind_affected_types::<M, W>,
+                        revert_wind_affected::<M, W>,
+                        setup_wind_affected::<M, W>,
+                    )
+                        .chain(),
+                    update_textures::<M, W>
+                        .after(texture::regenerate_wind_texture)
+                        .after(flow::regenerate_wind_flow_texture)
+                        .after(volume::regenerate_wind_noise_volume),
+                    (
+                        update_materials::<M, W>.run_if(resource_changed::<Wind>),
+                        update_type_overrides::<M, W>
+                            .run_if(resource_changed::<WindAffectedTypes<W>>),
+                        update_entity_overrides::<M, W>,
+                    )
+                        .chain()
+                        .after(transition::update_wind_transition),
+                ),
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    update_zones::<M, W>
+                        .run_if(resource_changed::<WindZones>)
+                        .after(zone::collect_wind_zones),
+                    collect_phase_slots::<M, W>.before(clock::update_wind_phases),
+                ),
+            );
+    }
+}
+
+/// Resources and systems that don't depend on the material type.
+struct WindCorePlugin;
+
+impl Plugin for WindCorePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Wind>()
             .register_type::<Wind>()
-            .init_resource::<WindAffectedTypes<W>>()
-            .add_systems(Startup, setup_wind_texture)
+            .register_type::<WindSeed>()
+            .register_type::<WindTextureSettings>()
+            .register_type::<WindZone>()
+            .register_type::<WindOverride>()
+            .register_type::<FoliageInteractor>()
+            .register_type::<WindPrototypeName>()
+            .init_resource::<WindZones>()
+            .init_resource::<WindImpulses>()
+            .init_resource::<FoliageInteractors>()
+            .init_resource::<TrampleMap>()
+            .init_resource::<WindClock>()
+            .init_resource::<WindPhases>()
+            .init_resource::<WindSeed>()
+            .init_resource::<WindTextureSettings>()
+            .init_resource::<texture::PreparedNoiseTextures>()
+            .add_event::<WindTransitionFinished>()
+            .add_event::<WindImpulse>()
+            .add_event::<WindTextureReady>()
+            .add_systems(
+                Startup,
+                (
+                    texture::setup_wind_texture,
+                    flow::setup_wind_flow_texture,
+                    trample::setup_trample_texture,
+                    volume::setup_wind_noise_volume,
+                    shared::setup_wind_shared,
+                ),
+            )
             .add_systems(
                 Update,
                 (
-                    setup_wind_affected::<M, W>,
-                    update_materials::<M, W>.run_if(resource_changed::<Wind>),
+                    texture::regenerate_wind_texture,
+                    texture::update_prepared_noise_textures,
+                    flow::regenerate_wind_flow_texture,
+                    volume::regenerate_wind_noise_volume,
+                    (clock::update_wind_clock, transition::update_wind_transition).chain(),
                 ),
-            );
+            )
+            .add_systems(
+                PostUpdate,
+                (
+                    zone::collect_wind_zones,
+                    interactor::collect_foliage_interactors,
+                    trample::update_trample_map,
+                )
+                    .after(TransformSystem::TransformPropagate),
+            )
+            .add_systems(PostUpdate, impulse::update_wind_impulses)
+            .add_systems(
+                PostUpdate,
+                (clock::update_wind_phases, shared::update_wind_shared)
+                    .chain()
+                    .after(impulse::update_wind_impulses)
+                    .after(interactor::collect_foliage_interactors)
+                    .after(trample::update_trample_map),
+            )
+            .add_plugins((
+                ExtractResourcePlugin::<WindShared>::default(),
+                ExtractResourcePlugin::<trample::TrampleTexels>::default(),
+            ));
+
+        let Some(render_app) = app.get_sub_app_mut(RenderApp) else {
+            return;
+        };
+
+        render_app.add_systems(
+            Render,
+            (shared::write_wind_shared, trample::write_trample_texture)
+                .in_set(RenderSet::PrepareResources),
+        );
+    }
+}
+
+/// Wind affected materials by source material and noise texture, so entities that shared a
+/// material still share one after the conversion.
+#[derive(Resource)]
+struct WindMaterialCache<M: Material, W: Material> {
+    values: HashMap<(AssetId<M>, AssetId<Image>), Vec<AssetId<W>>>,
+}
+
+impl<M: Material, W: Material> Default for WindMaterialCache<M, W> {
+    fn default() -> Self {
+        Self {
+            values: Default::default(),
+        }
+    }
+}
+
+impl<M: Material, W: WindAffectable<M, W> + Material> WindMaterialCache<M, W> {
+    /// Returns a live material with the same override, or adds the one returned by `create`.
+    ///
+    /// All wind affected materials are created through here, so every path shares them.
+    fn get_or_create(
+        &mut self,
+        key: (AssetId<M>, AssetId<Image>),
+        wind_override: &WindOverride,
+        materials: &mut Assets<W>,
+        create: impl FnOnce(&Assets<W>) -> Option<W>,
+    ) -> Option<Handle<W>> {
+        if let Some(material) = self.get(key, wind_override, materials) {
+            return Some(material);
+        }
+
+        let material = materials.add(create(materials)?);
+        self.values.entry(key).or_default().push(material.id());
+        Some(material)
+    }
+
+    /// Entries of dropped materials are pruned along the way.
+    ///
+    /// The override is read from the material, as type overrides are edited in place.
+    fn get(
+        &mut self,
+        key: (AssetId<M>, AssetId<Image>),
+        wind_override: &WindOverride,
+        materials: &mut Assets<W>,
+    ) -> Option<Handle<W>> {
+        let entries = self.values.get_mut(&key)?;
+        entries.retain(|id| materials.contains(*id));
+        if entries.is_empty() {
+            self.values.remove(&key);
+            return None;
+        }
+
+        let id = *entries.iter().find(|id| {
+            materials
+                .get(**id)
+                .is_some_and(|material| W::extension(material).wind_override == *wind_override)
+        })?;
+        materials.get_strong_handle(id)
+    }
+}
+
+/// Returns the source material, or `None` while it is still loading.
+///
+/// Materials added to [`Assets`] directly have no load state, so only a failed load is an error.
+fn load_source_material<'a, M: Material>(
+    handle: &Handle<M>,
+    materials: &'a Assets<M>,
+    asset_server: &AssetServer,
+) -> Option<Result<&'a M, WindConversionError>> {
+    if let Some(material) = materials.get(handle) {
+        return Some(Ok(material));
+    }
+
+    match asset_server.get_load_state(handle) {
+        Some(LoadState::Failed(err)) => Some(Err(WindConversionError::Load(err.to_string()))),
+        _ => None,
     }
 }
 
 fn create_material<M: Material, W: WindAffectable<M, W> + Material>(
     cmd: &mut Commands,
-    materials: &mut ResMut<Assets<M>>,
+    base: &M,
     extended_materials: &mut ResMut<Assets<W>>,
-    (entity, material, mesh): (Entity, &MeshMaterial3d<M>, &Mesh3d),
-    wind_noise_texture: &Res<WindTexture>,
+    cache: &mut WindMaterialCache<M, W>,
+    (entity, material, mesh, wind_override, name, parent_name): (
+        Entity,
+        &MeshMaterial3d<M>,
+        &Mesh3d,
+        Option<&WindOverride>,
+        Option<String>,
+        Option<String>,
+    ),
+    wind_noise_texture: Handle<Image>,
+    wind_flow_texture: &Res<WindFlowTexture>,
+    trample_texture: &Res<TrampleTexture>,
+    noise_volume: &Res<WindNoiseVolume>,
     wind: &Res<Wind>,
-) -> WindAffectedType<W> {
-    let new_material = W::create_material(
-        (*materials.get(material).unwrap()).clone(),
-        (*wind).clone(),
-        wind_noise_texture.0.clone(),
-    );
+    zones: &Res<WindZones>,
+    shared: &Res<WindShared>,
+    phases: &mut WindPhases,
+    images: &Assets<Image>,
+) -> Option<WindAffectedType<W>> {
+    let wind_override = wind_override.cloned().unwrap_or_default();
+    let wind = wind_override.apply(wind);
+    let source = material.0.clone();
+    let key = (source.id(), wind_noise_texture.id());
 
-    let material = extended_materials.add(new_material);
+    let material = cache.get_or_create(key, &wind_override, extended_materials, |_| {
+        Some(W::create_material(
+            base.clone(),
+            WindAffectedExtension {
+                wind: wind.clone(),
+                wind_override: wind_override.clone(),
+                zones: zones.values.clone(),
+                phase_slot: phases.slot::<W>(&wind_override),
+                noise_texture_size: images
+                    .get(&wind_noise_texture)
+                    .map(Image::size_f32)
+                    .unwrap_or(Vec2::ONE),
+                noise_texture: wind_noise_texture,
+                flow_texture: wind_flow_texture.0.clone(),
+                trample_texture: trample_texture.0.clone(),
+                noise_volume: noise_volume.0.clone(),
+                shared_buffer: shared.buffer.clone(),
+            },
+        ))
+    })?;
 
-    cmd.entity(entity)
-        .remove::<MeshMaterial3d<StandardMaterial>>()
-        .insert((MeshMaterial3d(material.clone()), WindAffectedReady));
+    cmd.entity(entity).remove::<MeshMaterial3d<M>>().insert((
+        MeshMaterial3d(material.clone()),
+        WindAffectedReady,
+        WindAffectedSource(source),
+    ));
 
-    WindAffectedType {
+    Some(WindAffectedType {
+        name,
+        parent_name,
         mesh: mesh.0.clone(),
         material,
-        wind: (*wind).clone(),
-    }
+        wind,
+        wind_override,
+    })
 }
 
 fn update_materials<M: Material, W: WindAffectable<M, W> + Material>(
     materials: ResMut<Assets<W>>,
+    mut types: ResMut<WindAffectedTypes<W>>,
     wind: Res<Wind>,
 ) {
     W::update_material(materials, wind.clone());
+
+    for wind_affected_type in types.bypass_change_detection().iter_mut() {
+        wind_affected_type.wind = wind_affected_type.wind_override.apply(&wind);
+    }
+}
+
+fn update_type_overrides<M: Material, W: WindAffectable<M, W> + Material>(
+    mut materials: ResMut<Assets<W>>,
+    mut types: ResMut<WindAffectedTypes<W>>,
+    mut phases: ResMut<WindPhases>,
+    wind: Res<Wind>,
+) {
+    for wind_affected_type in types.bypass_change_detection().iter_mut() {
+        let Some(material) = materials.get(&wind_affected_type.material) else {
+            continue;
+        };
+
+        if W::extension(material).wind_override == wind_affected_type.wind_override {
+            continue;
+        }
+
+        let wind_override = wind_affected_type.wind_override.clone();
+        wind_affected_type.wind = wind_override.apply(&wind);
+
+        if let Some(material) = materials.get_mut(&wind_affected_type.material) {
+            let ext = W::extension_mut(material);
+            ext.wind = wind_affected_type.wind.clone();
+            ext.phase_slot = phases.slot::<W>(&wind_override);
+            ext.wind_override = wind_override;
+        }
+    }
+}
+
+fn update_entity_overrides<M: Material, W: WindAffectable<M, W> + Material>(
+    mut cmd: Commands,
+    q: Query<
+        (
+            &MeshMaterial3d<W>,
+            &WindAffectedSource<M>,
+            Option<&WindOverride>,
+        ),
+        With<WindAffectedReady>,
+    >,
+    changed: Query<Entity, (Changed<WindOverride>, With<WindAffectedReady>)>,
+    mut removed: RemovedComponents<WindOverride>,
+    mut materials: ResMut<Assets<W>>,
+    mut cache: ResMut<WindMaterialCache<M, W>>,
+    mut phases: ResMut<WindPhases>,
+    wind: Res<Wind>,
+) {
+    for entity in changed.iter().chain(removed.read()) {
+        let Ok((material, source, wind_override)) = q.get(entity) else {
+            continue;
+        };
+        let Some(current) = materials.get(material) else {
+            continue;
+        };
+
+        // Compares the overrides, the wind itself also changes with gusts.
+        let wind_override = wind_override.cloned().unwrap_or_default();
+        let current_ext = W::extension(current);
+        if current_ext.wind_override == wind_override {
+            continue;
+        }
+
+        // Other entities may share the material, so entities with the same source material and
+        // override share a copy instead.
+        let key = (source.0.id(), current_ext.noise_texture.id());
+        let current = material.id();
+        let Some(material) =
+            cache.get_or_create(key, &wind_override, &mut materials, |materials| {
+                let mut new_material = materials.get(current)?.clone();
+                let ext = W::extension_mut(&mut new_material);
+                ext.wind = wind_override.apply(&wind);
+                ext.phase_slot = phases.slot::<W>(&wind_override);
+                ext.wind_override = wind_override.clone();
+                Some(new_material)
+            })
+        else {
+            continue;
+        };
+
+        cmd.entity(entity).insert(MeshMaterial3d(material));
+    }
+}
+
+fn update_zones<M: Material, W: WindAffectable<M, W> + Material>(
+    materials: ResMut<Assets<W>>,
+    zones: Res<WindZones>,
+) {
+    W::update_zones(materials, zones.clone());
+}
+
+/// Bind groups only pick up a modified image once the material itself changes, so the materials
+/// using it are touched and their noise texture size is refreshed along the way.
+fn update_textures<M: Material, W: WindAffectable<M, W> + Material>(
+    mut events: EventReader<AssetEvent<Image>>,
+    mut materials: ResMut<Assets<W>>,
+    images: Res<Assets<Image>>,
+) {
+    // Includes the per material noise textures, not only the global images.
+    let modified = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+
+    if modified.is_empty() {
+        return;
+    }
+
+    let affected = materials
+        .iter()
+        .filter(|(_, material)| {
+            let ext = W::extension(material);
+            [
+                &ext.noise_texture,
+                &ext.flow_texture,
+                &ext.trample_texture,
+                &ext.noise_volume,
+            ]
+            .into_iter()
+            .any(|handle| modified.contains(&handle.id()))
+        })
+        .map(|(id, _)| id)
+        .collect::<Vec<_>>();
+
+    for id in affected {
+        let Some(material) = materials.get_mut(id) else {
+            continue;
+        };
+        let ext = W::extension_mut(material);
+        if let Some(image) = images.get(&ext.noise_texture) {
+            ext.noise_texture_size = image.size_f32();
+        }
+    }
 }
 
 fn setup_wind_affected<M: Material, W: WindAffectable<M, W> + Material>(
     mut cmd: Commands,
     q: Query<
-        (Entity, &MeshMaterial3d<M>, &Mesh3d),
-        (With<WindAffected>, Without<WindAffectedReady>),
+        (
+            Entity,
+            &MeshMaterial3d<M>,
+            &Mesh3d,
+            Option<&WindOverride>,
+            Option<&WindNoiseTexture>,
+            Option<&WindPrototypeName>,
+            Option<&Name>,
+            Option<&ChildOf>,
+        ),
+        (
+            With<WindAffected>,
+            Without<WindAffectedReady>,
+            Without<WindAffectedFailed>,
+        ),
     >,
-    mut materials: ResMut<Assets<M>>,
+    materials: Res<Assets<M>>,
     mut extended_materials: ResMut<Assets<W>>,
-    mut types: ResMut<WindAffectedTypes<W>>,
-    wind_noise_texture: Res<WindTexture>,
+    (mut types, mut added_types): (
+        ResMut<WindAffectedTypes<W>>,
+        EventWriter<WindPrototypeAdded<W>>,
+    ),
+    mut cache: ResMut<WindMaterialCache<M, W>>,
+    mut images: ResMut<Assets<Image>>,
+    asset_server: Res<AssetServer>,
+    mut prepared_noise_textures: ResMut<texture::PreparedNoiseTextures>,
+    (wind_noise_texture, wind_flow_texture, trample_texture, noise_volume): (
+        Res<WindTexture>,
+        Res<WindFlowTexture>,
+        Res<TrampleTexture>,
+        Res<WindNoiseVolume>,
+    ),
     wind: Res<Wind>,
+    zones: Res<WindZones>,
+    shared: Res<WindShared>,
+    mut phases: ResMut<WindPhases>,
+    names: Query<&Name>,
 ) {
-    types.values.append(
-        &mut q
-            .iter()
-            .map(|x| {
+    let new_types = q
+        .iter()
+        .filter_map(
+            |(
+                entity,
+                material,
+                mesh,
+                wind_override,
+                noise_texture,
+                prototype_name,
+                name,
+                parent,
+            )| {
+                // Scenes can spawn entities before their material has loaded, those are retried.
+                let base = match load_source_material(&material.0, &materials, &asset_server)? {
+                    Ok(base) => base,
+                    Err(err) => {
+                        error!("{err}, {entity} keeps its material");
+                        cmd.entity(entity).insert(WindAffectedFailed);
+                        return None;
+                    }
+                };
+
+                // Entities with their own noise texture wait until it has loaded.
+                let noise_texture = match noise_texture {
+                    Some(noise_texture) => prepared_noise_textures.resolve(
+                        &noise_texture.0,
+                        &mut images,
+                        &asset_server,
+                        &wind_noise_texture.0,
+                    )?,
+                    None => wind_noise_texture.0.clone(),
+                };
+
                 create_material::<M, W>(
                     &mut cmd,
-                    &mut materials,
+                    base,
                     &mut extended_materials,
-                    x,
-                    &wind_noise_texture,
+                    &mut cache,
+                    (
+                        entity,
+                        material,
+                        mesh,
+                        wind_override,
+                        prototype_name
+                            .map(|prototype_name| prototype_name.0.clone())
+                            .or_else(|| name.map(|name| name.to_string())),
+                        parent
+                            .and_then(|parent| names.get(parent.parent()).ok())
+                            .map(|name| name.to_string()),
+                    ),
+                    noise_texture,
+                    &wind_flow_texture,
+                    &trample_texture,
+                    &noise_volume,
                     &wind,
+                    &zones,
+                    &shared,
+                    &mut phases,
+                    &images,
                 )
-            })
-            .collect(),
-    );
-}
+            },
+        )
+        .collect::<Vec<_>>();
 
-fn setup_wind_texture(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
-    let texture_size = 512;
-    let mut image_buffer = Vec::with_capacity((texture_size * texture_size) as usize);
+    for new_type in new_types {
+        if let Some(added) = types.insert(new_type) {
+            added_types.write(WindPrototypeAdded::from(added));
+        }
+    }
+}
 
-    let perlin = Perlin::new(1);
+/// Restores the original material of entities that had [`WindAffected`] removed, the prototypes of
+/// their materials are dropped once no other entity uses them.
+fn revert_wind_affected<M: Material, W: WindAffectable<M, W> + Material>(
+    mut cmd: Commands,
+    mut removed: RemovedComponents<WindAffected>,
+    q: Query<(&WindAffectedSource<M>, &MeshMaterial3d<W>), With<WindAffectedReady>>,
+    failed: Query<(), With<WindAffectedFailed>>,
+    in_use: Query<(Entity, &MeshMaterial3d<W>)>,
+    mut types: ResMut<WindAffectedTypes<W>>,
+    mut removed_types: EventWriter<WindPrototypeRemoved<W>>,
+) {
+    let mut reverted = EntityHashSet::default();
+    let mut reverted_materials = HashSet::new();
+    for entity in removed.read() {
+        // Re-adding `WindAffected` retries a failed conversion.
+        if failed.contains(entity) {
+            cmd.entity(entity).remove::<WindAffectedFailed>();
+        }
 
-    for y in 0..texture_size {
-        for x in 0..texture_size {
-            let sample_scale = 5.0;
-            let point = [
-                x as f64 / texture_size as f64 * sample_scale,
-                y as f64 / texture_size as f64 * sample_scale,
-            ];
+        let Ok((source, material)) = q.get(entity) else {
+            continue;
+        };
 
-            let noise_value = perlin.get(point);
+        cmd.entity(entity)
+            .remove::<(MeshMaterial3d<W>, WindAffectedReady, WindAffectedSource<M>)>()
+            .insert(MeshMaterial3d(source.0.clone()));
+        reverted.insert(entity);
+        reverted_materials.insert(material.id());
+    }
 
-            let byte = ((noise_value * 0.5 + 0.5) * 255.0) as u8;
-            image_buffer.push(byte);
-        }
+    if reverted.is_empty() {
+        return;
     }
 
-    let mut wind_image = Image::new(
-        Extent3d {
-            width: texture_size,
-            height: texture_size,
-            depth_or_array_layers: 1,
-        },
-        TextureDimension::D2,
-        image_buffer,
-        TextureFormat::R8Unorm,
-        default(),
-    );
-
-    let sampler_descriptor = ImageSampler::Descriptor(ImageSamplerDescriptor {
-        label: Some("Wind Noise Sampler".into()),
-        address_mode_u: ImageAddressMode::MirrorRepeat,
-        address_mode_v: ImageAddressMode::MirrorRepeat,
-        address_mode_w: ImageAddressMode::MirrorRepeat,
-        ..default()
+    let used = in_use
+        .iter()
+        .filter(|(entity, _)| !reverted.contains(entity))
+        .map(|(_, material)| material.id())
+        .collect::<HashSet<_>>();
+
+    remove_types(&mut types, &mut removed_types, |wind_affected_type| {
+        let material = wind_affected_type.material.id();
+        reverted_materials.contains(&material) && !used.contains(&material)
     });
+}
 
-    wind_image.sampler = sampler_descriptor;
+/// Frees the phase slots of overrides that no material uses after materials were changed or
+/// removed.
+fn collect_phase_slots<M: Material, W: WindAffectable<M, W> + Material>(
+    mut events: EventReader<AssetEvent<W>>,
+    materials: Res<Assets<W>>,
+    mut phases: ResMut<WindPhases>,
+) {
+    if events.read().count() == 0 {
+        return;
+    }
+
+    let slots = materials
+        .iter()
+        .map(|(_, material)| W::extension(material).phase_slot)
+        .collect();
+    phases.set_used::<W>(slots);
+}
+
+/// Drops prototypes whose mesh or material has been removed from its assets.
+fn prune_wind_affected_types<M: Material, W: WindAffectable<M, W> + Material>(
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    mut material_events: EventReader<AssetEvent<W>>,
+    mut types: ResMut<WindAffectedTypes<W>>,
+    mut removed_types: EventWriter<WindPrototypeRemoved<W>>,
+) {
+    let meshes = mesh_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Removed { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
+    let materials = material_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Removed { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<HashSet<_>>();
 
-    let handle = images.add(wind_image);
+    if meshes.is_empty() && materials.is_empty() {
+        return;
+    }
+
+    remove_types(&mut types, &mut removed_types, |wind_affected_type| {
+        meshes.contains(&wind_affected_type.mesh.id())
+            || materials.contains(&wind_affected_type.material.id())
+    });
+}
+
+fn remove_types<W: Material>(
+    types: &mut ResMut<WindAffectedTypes<W>>,
+    removed_types: &mut EventWriter<WindPrototypeRemoved<W>>,
+    predicate: impl Fn(&WindAffectedType<W>) -> bool,
+) {
+    let removed = types.bypass_change_detection().remove_where(predicate);
+    if removed.is_empty() {
+        return;
+    }
 
-    commands.insert_resource(WindTexture(handle));
+    types.set_changed();
+    removed_types.write_batch(removed.iter().map(WindPrototypeRemoved::from));
 }
@@ -3,12 +3,35 @@ use std::marker::PhantomData;
 
 use bevy::prelude::*;
 use bevy::render::render_resource::ShaderType;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
 
+pub use crate::clock::*;
 pub use crate::extension::*;
+pub use crate::flow::*;
+pub use crate::gust::*;
+pub use crate::impulse::*;
+pub use crate::interactor::*;
+pub use crate::preset::*;
+pub use crate::sampler::*;
+pub use crate::shared::*;
+pub use crate::texture::*;
+pub use crate::trample::*;
+pub use crate::transition::*;
+pub use crate::unlit::*;
+pub use crate::volume::*;
+pub use crate::wind_override::*;
+pub use crate::zone::*;
 
+/// Registry of the prototypes converted so far, one per mesh and material pair.
+///
+/// The registry holds strong handles, so prototypes stay spawnable after every entity using them
+/// is despawned. An entry is dropped when the entities using its material have [`WindAffected`]
+/// removed, or when its mesh or material is removed with [`Assets::remove`]. See
+/// [`WindPrototypeAdded`] and [`WindPrototypeRemoved`].
 #[derive(Resource)]
 pub struct WindAffectedTypes<M: Material> {
-    pub values: Vec<WindAffectedType<M>>,
+    values: Vec<WindAffectedType<M>>,
     pub _marker: PhantomData<M>,
 }
 
@@ -22,31 +45,178 @@ impl<M: Material> Default for WindAffectedTypes<M> {
 }
 
 pub struct WindAffectedType<M: Material> {
+    /// From the [`WindPrototypeName`] or [`Name`] of the first entity using the prototype.
+    pub name: Option<String>,
+    /// From the [`Name`] of the parent of that entity, glTF scenes keep the node name there
+    /// and name the mesh entities after the mesh, e.g. "Mesh.0".
+    pub parent_name: Option<String>,
     pub mesh: Handle<Mesh>,
     pub material: Handle<M>,
     pub wind: Wind,
+    pub wind_override: WindOverride,
+}
+
+impl<M: Material> WindAffectedType<M> {
+    fn has_name(&self, name: &str) -> bool {
+        self.name.as_deref() == Some(name) || self.parent_name.as_deref() == Some(name)
+    }
 }
 
 impl<M: Material> WindAffectedTypes<M> {
     pub fn get(&self) -> &Vec<WindAffectedType<M>> {
         &self.values
     }
+
+    /// First prototype registered under `name` or with a parent of that name.
+    pub fn get_by_name(&self, name: &str) -> Option<&WindAffectedType<M>> {
+        self.values
+            .iter()
+            .find(|wind_affected_type| wind_affected_type.has_name(name))
+    }
+
+    /// Like [`Self::get_by_name`], changing its `wind_override` re-applies it to the material.
+    pub fn get_by_name_mut(&mut self, name: &str) -> Option<&mut WindAffectedType<M>> {
+        self.values
+            .iter_mut()
+            .find(|wind_affected_type| wind_affected_type.has_name(name))
+    }
+
+    /// All prototypes registered under `name` or with a parent of that name, e.g. every
+    /// primitive of a glTF node.
+    pub fn iter_by_name<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a WindAffectedType<M>> + 'a {
+        self.values
+            .iter()
+            .filter(move |wind_affected_type| wind_affected_type.has_name(name))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.values
+            .iter()
+            .filter_map(|wind_affected_type| wind_affected_type.name.as_deref())
+    }
+
+    /// Changing a prototype's `wind_override` through [`ResMut`] re-applies it to the material.
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut WindAffectedType<M>> {
+        self.values.iter_mut()
+    }
+
+    /// Adds the prototype unless one with the same mesh and material exists.
+    pub(crate) fn insert(&mut self, new_type: WindAffectedType<M>) -> Option<&WindAffectedType<M>> {
+        let exists = self.values.iter().any(|wind_affected_type| {
+            wind_affected_type.mesh == new_type.mesh
+                && wind_affected_type.material == new_type.material
+        });
+        if exists {
+            return None;
+        }
+
+        self.values.push(new_type);
+        self.values.last()
+    }
+
+    /// Removes and returns the prototypes matching `predicate`.
+    pub(crate) fn remove_where(
+        &mut self,
+        predicate: impl Fn(&WindAffectedType<M>) -> bool,
+    ) -> Vec<WindAffectedType<M>> {
+        let (removed, kept) = std::mem::take(&mut self.values)
+            .into_iter()
+            .partition(|wind_affected_type| predicate(wind_affected_type));
+        self.values = kept;
+        removed
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct WindPrototypeAdded<M: Material> {
+    pub name: Option<String>,
+    pub mesh: AssetId<Mesh>,
+    pub material: AssetId<M>,
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct WindPrototypeRemoved<M: Material> {
+    pub name: Option<String>,
+    pub mesh: AssetId<Mesh>,
+    pub material: AssetId<M>,
+}
+
+impl<M: Material> From<&WindAffectedType<M>> for WindPrototypeAdded<M> {
+    fn from(wind_affected_type: &WindAffectedType<M>) -> Self {
+        Self {
+            name: wind_affected_type.name.clone(),
+            mesh: wind_affected_type.mesh.id(),
+            material: wind_affected_type.material.id(),
+        }
+    }
+}
+
+impl<M: Material> From<&WindAffectedType<M>> for WindPrototypeRemoved<M> {
+    fn from(wind_affected_type: &WindAffectedType<M>) -> Self {
+        Self {
+            name: wind_affected_type.name.clone(),
+            mesh: wind_affected_type.mesh.id(),
+            material: wind_affected_type.material.id(),
+        }
+    }
 }
 
+/// Registers the entity's prototype under this name instead of its [`Name`], e.g. to group
+/// meshes as "tall_grass" for scattering.
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+pub struct WindPrototypeName(pub String);
+
 #[derive(Component)]
 pub struct WindAffectedReady;
 
+/// Converts the entity's material to the wind affected one, removing it restores the original.
 #[derive(Component, Reflect)]
 #[reflect(Component)]
 pub struct WindAffected;
 
+/// Added instead of [`WindAffectedReady`] when the conversion failed. Removing it, or removing
+/// and re-adding [`WindAffected`], retries.
+#[derive(Component, Debug)]
+pub struct WindAffectedFailed;
+
+#[derive(Debug, Error)]
+pub enum WindConversionError {
+    #[error("Source material failed to load: {0}")]
+    Load(String),
+}
+
+/// The material an entity used before it became [`WindAffectedReady`].
+#[derive(Component, Debug, Clone)]
+pub struct WindAffectedSource<M: Material>(pub Handle<M>);
+
+/// Noise sampled by the wind shaders, a flat placeholder until [`WindTextureReady`] is sent.
 #[derive(Resource)]
 pub struct WindTexture(pub Handle<Image>);
 
-#[derive(Resource, Debug, Clone, Reflect)]
+/// Seeds the generated wind noise and gusts. The same seed and [`WindClock`] time always give the
+/// same displacement, on the GPU as well as through the [`WindSampler`], see [`WindPhases`] for
+/// how changing speeds are followed.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect)]
+#[reflect(Resource)]
+pub struct WindSeed(pub u32);
+
+impl Default for WindSeed {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+#[derive(Resource, Debug, Clone, PartialEq, Reflect, Serialize, Deserialize)]
 #[reflect(Resource)]
+#[serde(default)]
 pub struct Wind {
     pub direction: Vec2,
+    /// Vertical component added to `direction`, 0.0 keeps the wind horizontal.
+    pub vertical: f32,
     pub strength: f32,
     pub noise_scale: f32,
     pub scroll_speed: f32,
@@ -65,11 +235,30 @@ pub struct Wind {
     pub enable_edge_correction: bool,
     pub edge_correction_factor: f32,
     pub lod_threshold: f32,
+    /// Bends along the direction sampled from [`WindFlowTexture`] instead of `direction`.
+    pub enable_flow_field: bool,
+    pub flow_scale: f32,
+    pub flow_scroll_speed: f32,
+    /// Samples the macro noise from the [`WindNoiseVolume`], so gusts change shape as they move.
+    pub enable_noise_volume: bool,
+    /// Loops through the volume per second.
+    pub volume_speed: f32,
+    pub phase_seed: WindPhaseSeed,
+}
+
+#[derive(ShaderType, Clone)]
+pub struct WindMaterialUniform {
+    pub wind: WindUniform,
+    pub zones: [WindZoneUniform; MAX_WIND_ZONES],
+    pub noise_texture_size: Vec2,
+    pub zone_count: u32,
+    pub phase_slot: u32,
 }
 
 #[derive(ShaderType, Clone)]
 pub struct WindUniform {
     pub direction: Vec2,
+    pub vertical: f32,
     pub strength: f32,
     pub noise_scale: f32,
     pub scroll_speed: f32,
@@ -88,6 +277,12 @@ pub struct WindUniform {
     pub enable_edge_correction: u32,
     pub edge_correction_factor: f32,
     pub lod_threshold: f32,
+    pub enable_flow_field: u32,
+    pub flow_scale: f32,
+    pub flow_scroll_speed: f32,
+    pub enable_noise_volume: u32,
+    pub volume_speed: f32,
+    pub phase_seed: u32,
 }
 
 impl Default for Wind {
@@ -95,6 +290,7 @@ impl Default for Wind {
         let direction = Vec2::new(1.0, 0.5).normalize();
         Self {
             direction,
+            vertical: 0.0,
             strength: 1.0,
             noise_scale: 0.02,
             scroll_speed: 0.2,
@@ -113,6 +309,12 @@ impl Default for Wind {
             enable_edge_correction: false,
             lod_threshold: 50.0,
             edge_correction_factor: 0.01,
+            enable_flow_field: false,
+            flow_scale: 0.05,
+            flow_scroll_speed: 0.02,
+            enable_noise_volume: false,
+            volume_speed: 0.02,
+            phase_seed: WindPhaseSeed::Position,
         }
     }
 }
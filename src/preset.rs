@@ -0,0 +1,218 @@
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+use bevy::asset::{AssetLoader, LoadContext, io::Reader};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::prelude::*;
+
+pub struct WindPresetPlugin;
+
+impl Plugin for WindPresetPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<WindPreset>()
+            .init_asset_loader::<WindPresetLoader>()
+            .add_event::<SaveWindPreset>()
+            .add_systems(Update, (apply_wind_preset, save_wind_preset));
+    }
+}
+
+#[derive(Asset, TypePath, Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(transparent)]
+pub struct WindPreset(pub Wind);
+
+/// The preset applied to [`Wind`], re-applied whenever the asset is (re)loaded.
+#[derive(Resource, Debug, Clone)]
+pub struct WindPresetHandle(pub Handle<WindPreset>);
+
+#[derive(Event, Debug, Clone)]
+pub struct SaveWindPreset {
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Error)]
+pub enum WindPresetError {
+    #[error("Could not read or write wind preset: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("Could not parse RON wind preset: {0}")]
+    RonParse(#[from] ron::error::SpannedError),
+    #[error("Could not serialize RON wind preset: {0}")]
+    Ron(#[from] ron::Error),
+    #[error("Could not parse or serialize JSON wind preset: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Wind preset files must end in .wind.ron or .wind.json: {0}")]
+    UnsupportedFormat(PathBuf),
+}
+
+enum WindPresetFormat {
+    Ron,
+    Json,
+}
+
+impl WindPresetFormat {
+    /// Only the extensions registered by [`WindPresetLoader`], so saved presets can be loaded.
+    fn from_path(path: &Path) -> Result<Self, WindPresetError> {
+        let file_name = path.file_name().and_then(OsStr::to_str).unwrap_or_default();
+        if file_name.ends_with(".wind.ron") {
+            Ok(Self::Ron)
+        } else if file_name.ends_with(".wind.json") {
+            Ok(Self::Json)
+        } else {
+            Err(WindPresetError::UnsupportedFormat(path.to_path_buf()))
+        }
+    }
+}
+
+impl WindPreset {
+    /// Writes RON or JSON depending on whether `path` ends in `.wind.ron` or `.wind.json`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WindPresetError> {
+        let path = path.as_ref();
+        let contents = match WindPresetFormat::from_path(path)? {
+            WindPresetFormat::Ron => {
+                ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?
+            }
+            WindPresetFormat::Json => serde_json::to_string_pretty(self)?,
+        };
+
+        std::fs::write(path, contents)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Default)]
+pub struct WindPresetLoader;
+
+impl AssetLoader for WindPresetLoader {
+    type Asset = WindPreset;
+    type Settings = ();
+    type Error = WindPresetError;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        load_context: &mut LoadContext<'_>,
+    ) -> Result<Self::Asset, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        Ok(match WindPresetFormat::from_path(load_context.path())? {
+            WindPresetFormat::Ron => ron::de::from_bytes(&bytes)?,
+            WindPresetFormat::Json => serde_json::from_slice(&bytes)?,
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["wind.ron", "wind.json"]
+    }
+}
+
+pub(crate) fn apply_wind_preset(
+    preset: Option<Res<WindPresetHandle>>,
+    presets: Res<Assets<WindPreset>>,
+    mut events: EventReader<AssetEvent<WindPreset>>,
+    mut wind: ResMut<Wind>,
+) {
+    let reloaded = events
+        .read()
+        .filter(|event| {
+            preset.as_ref().is_some_and(|preset| {
+                event.is_loaded_with_dependencies(&preset.0) || event.is_modified(&preset.0)
+            })
+        })
+        .count()
+        > 0;
+
+    let Some(preset) = preset else {
+        return;
+    };
+
+    if !reloaded && !preset.is_changed() {
+        return;
+    }
+
+    if let Some(preset) = presets.get(&preset.0) {
+        *wind = preset.0.clone();
+    }
+}
+
+fn save_wind_preset(mut events: EventReader<SaveWindPreset>, wind: Res<Wind>) {
+    for event in events.read() {
+        match WindPreset(wind.clone()).save(&event.path) {
+            Ok(()) => info!("Saved wind preset to {}", event.path.display()),
+            Err(err) => error!("Failed to save wind preset: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn preset() -> WindPreset {
+        WindPreset(Wind {
+            strength: 2.5,
+            direction: Vec2::Y,
+            enable_flow_field: true,
+            ..default()
+        })
+    }
+
+    #[test]
+    fn format_follows_loader_extensions() {
+        assert!(matches!(
+            WindPresetFormat::from_path(Path::new("presets/calm.wind.ron")),
+            Ok(WindPresetFormat::Ron)
+        ));
+        assert!(matches!(
+            WindPresetFormat::from_path(Path::new("presets/calm.wind.json")),
+            Ok(WindPresetFormat::Json)
+        ));
+        assert!(matches!(
+            WindPresetFormat::from_path(Path::new("presets/calm.ron")),
+            Err(WindPresetError::UnsupportedFormat(_))
+        ));
+        assert!(matches!(
+            WindPresetFormat::from_path(Path::new("presets/wind.ron")),
+            Err(WindPresetError::UnsupportedFormat(_))
+        ));
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let ron = ron::ser::to_string_pretty(&preset(), ron::ser::PrettyConfig::default()).unwrap();
+        let loaded: WindPreset = ron::de::from_str(&ron).unwrap();
+
+        assert_eq!(loaded.0, preset().0);
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let json = serde_json::to_string_pretty(&preset()).unwrap();
+        let loaded: WindPreset = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(loaded.0, preset().0);
+    }
+
+    #[test]
+    fn save_writes_loadable_presets() {
+        let path =
+            std::env::temp_dir().join(format!("wind_preset_{}.wind.ron", std::process::id()));
+        preset().save(&path).unwrap();
+        let loaded: WindPreset = ron::de::from_bytes(&std::fs::read(&path).unwrap()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.0, preset().0);
+    }
+
+    #[test]
+    fn save_rejects_unloadable_paths() {
+        let path = std::env::temp_dir().join(format!("wind_preset_{}.ron", std::process::id()));
+
+        assert!(preset().save(&path).is_err());
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,294 @@
+use bevy::ecs::system::SystemParam;
+use bevy::image::{ImageAddressMode, ImageFilterMode, ImageSampler};
+use bevy::prelude::*;
+use bevy::render::render_resource::TextureDimension;
+
+use crate::prelude::*;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WindSample {
+    pub macro_noise: f32,
+    pub micro_noise: f32,
+    pub phase_noise: Vec2,
+    /// World space offset of a vertex at the sampled height.
+    pub displacement: Vec3,
+}
+
+/// Samples the wind on the CPU, mirroring `calculate_vertex_displacement` in `wind_displace.wgsl`.
+#[derive(SystemParam)]
+pub struct WindSampler<'w> {
+    wind: Res<'w, Wind>,
+    zones: Res<'w, WindZones>,
+    impulses: Res<'w, WindImpulses>,
+    interactors: Res<'w, FoliageInteractors>,
+    trample: Res<'w, TrampleMap>,
+    clock: Res<'w, WindClock>,
+    phases: Res<'w, WindPhases>,
+    texture: Option<Res<'w, WindTexture>>,
+    flow_texture: Option<Res<'w, WindFlowTexture>>,
+    noise_volume: Option<Res<'w, WindNoiseVolume>>,
+    images: Res<'w, Assets<Image>>,
+}
+
+impl WindSampler<'_> {
+    /// Samples at the tip (normalized height of 1.0) of a plant placed at `world_pos`, at the
+    /// current [`WindClock`] time.
+    pub fn sample_now(&self, world_pos: Vec3) -> WindSample {
+        self.sample(world_pos, self.clock.elapsed())
+    }
+
+    /// Samples at the tip (normalized height of 1.0) of a plant placed at `world_pos`, at
+    /// [`WindClock`] time `time`. See [`WindPhases::at`] for how far from the current time the
+    /// animation is exact.
+    pub fn sample(&self, world_pos: Vec3, time: f64) -> WindSample {
+        self.sample_at_height(world_pos, 1.0, time)
+    }
+
+    pub fn sample_at_height(&self, world_pos: Vec3, height: f32, time: f64) -> WindSample {
+        self.sample_with(&self.wind, world_pos, height, time)
+    }
+
+    pub fn sample_with(&self, wind: &Wind, world_pos: Vec3, height: f32, time: f64) -> WindSample {
+        let phases = self.phases.at(wind, &self.wind, time);
+        let position = world_pos.xz();
+
+        let macro_coord = position * wind.noise_scale + phases.macro_offset;
+        let micro_coord = position * wind.micro_noise_scale + phases.micro_offset;
+
+        let macro_noise = match wind.enable_noise_volume {
+            true => self.sample_volume(macro_coord.extend(phases.volume_offset)),
+            false => self.sample_noise(macro_coord).x,
+        };
+        let micro_noise = self.sample_noise(micro_coord).y;
+        let phase_noise = self.sample_noise(phase_coord(world_pos)).zw();
+
+        let mut wind = wind.clone();
+        if wind.enable_flow_field {
+            let flow_coord = position * wind.flow_scale + phases.flow_offset;
+            let flow = self.sample_flow(flow_coord) * 2.0 - 1.0;
+            if flow.length() > 0.0001 {
+                wind.direction = flow.normalize();
+            }
+        }
+
+        let wind = self.zones.apply(&wind, world_pos);
+        let c_curve_shape = height.powf(wind.bend_exponent);
+        let wind_dir = Vec3::new(wind.direction.x, wind.vertical, wind.direction.y);
+
+        let macro_displacement = (macro_noise * 2.0 - 1.0) * wind.strength * c_curve_shape;
+        let micro_displacement = (micro_noise * 2.0 - 1.0) * wind.micro_strength * c_curve_shape;
+
+        let s_curve_anim = (phases.s_curve_phase + phase_noise.x * std::f32::consts::TAU).sin();
+        let s_curve_wiggles = (height * wind.s_curve_frequency).sin();
+        let s_curve_shape = c_curve_shape + s_curve_wiggles * wind.s_curve_strength * c_curve_shape;
+        let s_curve = s_curve_anim * wind.s_curve_strength * s_curve_shape;
+
+        let external = (self.impulses.displacement(world_pos, time)
+            + self.interactors.displacement(world_pos)
+            + self.trample.displacement(world_pos))
+            * c_curve_shape;
+
+        let bop = (phases.bop_phase + phase_noise.y * std::f32::consts::TAU).sin()
+            * wind.bop_strength
+            * c_curve_shape;
+
+        WindSample {
+            macro_noise,
+            micro_noise,
+            phase_noise,
+            displacement: wind_dir * (macro_displacement + micro_displacement + s_curve)
+                + Vec3::Y * bop
+                + external,
+        }
+    }
+
+    /// Reads the noise texture the same way the sampler in the shaders does, always from the full
+    /// resolution level while the shaders move to coarser mips with distance.
+    pub fn sample_noise(&self, uv: Vec2) -> Vec4 {
+        self.texture
+            .as_ref()
+            .and_then(|texture| self.images.get(&texture.0))
+            .map(|image| sample_image(image, uv.extend(0.0)))
+            .unwrap_or(Vec4::splat(0.5))
+    }
+
+    /// Reads the [`WindNoiseVolume`], with time slices along z.
+    pub fn sample_volume(&self, uvw: Vec3) -> f32 {
+        self.noise_volume
+            .as_ref()
+            .and_then(|volume| self.images.get(&volume.0))
+            .map(|image| sample_image(image, uvw).x)
+            .unwrap_or(0.5)
+    }
+
+    /// Reads the flow field direction, still remapped to 0..1.
+    pub fn sample_flow(&self, uv: Vec2) -> Vec2 {
+        self.flow_texture
+            .as_ref()
+            .and_then(|texture| self.images.get(&texture.0))
+            .map(|image| sample_image(image, uv.extend(0.0)).xy())
+            .unwrap_or(Vec2::splat(0.5))
+    }
+}
+
+/// Matches `calculate_phase_coord` in `wind_displace.wgsl` for [`WindPhaseSeed::Position`].
+fn phase_coord(position: Vec3) -> Vec2 {
+    position.xz() * 7.31
+}
+
+/// Samples a 2D or 3D image, `uvw.z` is ignored for 2D images.
+fn sample_image(image: &Image, uvw: Vec3) -> Vec4 {
+    let (address_mode_u, address_mode_v, address_mode_w, filter) = match &image.sampler {
+        ImageSampler::Descriptor(descriptor) => (
+            descriptor.address_mode_u,
+            descriptor.address_mode_v,
+            descriptor.address_mode_w,
+            descriptor.mag_filter,
+        ),
+        ImageSampler::Default => (
+            ImageAddressMode::ClampToEdge,
+            ImageAddressMode::ClampToEdge,
+            ImageAddressMode::ClampToEdge,
+            ImageFilterMode::Linear,
+        ),
+    };
+
+    let depth = match image.texture_descriptor.dimension {
+        TextureDimension::D3 => image.texture_descriptor.size.depth_or_array_layers,
+        _ => 1,
+    };
+    let size = image.size().extend(depth);
+    let texel = uvw * size.as_vec3();
+
+    match filter {
+        ImageFilterMode::Nearest => {
+            let x = wrap_texel(texel.x.floor() as i32, size.x, address_mode_u);
+            let y = wrap_texel(texel.y.floor() as i32, size.y, address_mode_v);
+            let z = wrap_texel(texel.z.floor() as i32, size.z, address_mode_w);
+            read_texel(image, UVec3::new(x, y, z))
+        }
+        ImageFilterMode::Linear => {
+            let texel = texel - 0.5;
+            let base = texel.floor();
+            let t = texel - base;
+            let x0 = wrap_texel(base.x as i32, size.x, address_mode_u);
+            let x1 = wrap_texel(base.x as i32 + 1, size.x, address_mode_u);
+            let y0 = wrap_texel(base.y as i32, size.y, address_mode_v);
+            let y1 = wrap_texel(base.y as i32 + 1, size.y, address_mode_v);
+
+            let slice = |z: i32| {
+                let z = wrap_texel(z, size.z, address_mode_w);
+                let top = read_texel(image, UVec3::new(x0, y0, z))
+                    .lerp(read_texel(image, UVec3::new(x1, y0, z)), t.x);
+                let bottom = read_texel(image, UVec3::new(x0, y1, z))
+                    .lerp(read_texel(image, UVec3::new(x1, y1, z)), t.x);
+                top.lerp(bottom, t.y)
+            };
+
+            if size.z == 1 {
+                return slice(0);
+            }
+            slice(base.z as i32).lerp(slice(base.z as i32 + 1), t.z)
+        }
+    }
+}
+
+fn wrap_texel(texel: i32, size: u32, address_mode: ImageAddressMode) -> u32 {
+    let size = size as i32;
+
+    let texel = match address_mode {
+        ImageAddressMode::Repeat => texel.rem_euclid(size),
+        ImageAddressMode::MirrorRepeat => {
+            let mirrored = texel.rem_euclid(size * 2);
+            if mirrored >= size {
+                size * 2 - 1 - mirrored
+            } else {
+                mirrored
+            }
+        }
+        _ => texel.clamp(0, size - 1),
+    };
+
+    texel as u32
+}
+
+fn read_texel(image: &Image, texel: UVec3) -> Vec4 {
+    let Some(bytes) = image.pixel_bytes(texel) else {
+        return Vec4::ZERO;
+    };
+
+    // Missing channels read as (0, 0, 0, 1) on the GPU.
+    let mut texel = Vec4::new(0.0, 0.0, 0.0, 1.0);
+    for (channel, byte) in bytes.iter().take(4).enumerate() {
+        texel[channel] = *byte as f32 / 255.0;
+    }
+    texel
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::image::ImageSamplerDescriptor;
+    use bevy::render::render_resource::{Extent3d, TextureFormat};
+
+    use super::*;
+
+    fn image(texels: &[u8], sampler: ImageSampler) -> Image {
+        let mut image = Image::new(
+            Extent3d {
+                width: texels.len() as u32,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            texels.to_vec(),
+            TextureFormat::R8Unorm,
+            default(),
+        );
+        image.sampler = sampler;
+        image
+    }
+
+    #[test]
+    fn wrap_texel_follows_address_mode() {
+        assert_eq!(wrap_texel(5, 4, ImageAddressMode::Repeat), 1);
+        assert_eq!(wrap_texel(-1, 4, ImageAddressMode::Repeat), 3);
+        assert_eq!(wrap_texel(4, 4, ImageAddressMode::MirrorRepeat), 3);
+        assert_eq!(wrap_texel(-1, 4, ImageAddressMode::MirrorRepeat), 0);
+        assert_eq!(wrap_texel(9, 4, ImageAddressMode::ClampToEdge), 3);
+        assert_eq!(wrap_texel(-2, 4, ImageAddressMode::ClampToEdge), 0);
+    }
+
+    #[test]
+    fn sample_image_filters_linearly_between_texel_centres() {
+        let image = image(&[0, 255], ImageSampler::Default);
+
+        assert_eq!(sample_image(&image, Vec3::new(0.25, 0.5, 0.0)).x, 0.0);
+        assert_eq!(sample_image(&image, Vec3::new(0.5, 0.5, 0.0)).x, 0.5);
+        assert_eq!(sample_image(&image, Vec3::new(1.0, 0.5, 0.0)).x, 1.0);
+    }
+
+    #[test]
+    fn sample_image_wraps_nearest_texels() {
+        let image = image(
+            &[0, 255],
+            ImageSampler::Descriptor(ImageSamplerDescriptor {
+                address_mode_u: ImageAddressMode::Repeat,
+                mag_filter: ImageFilterMode::Nearest,
+                ..default()
+            }),
+        );
+
+        assert_eq!(sample_image(&image, Vec3::new(1.25, 0.5, 0.0)).x, 0.0);
+        assert_eq!(sample_image(&image, Vec3::new(-0.25, 0.5, 0.0)).x, 1.0);
+    }
+
+    #[test]
+    fn missing_channels_read_as_opaque_black() {
+        let image = image(&[255], ImageSampler::Default);
+
+        assert_eq!(
+            sample_image(&image, Vec3::splat(0.5)),
+            Vec4::new(1.0, 0.0, 0.0, 1.0)
+        );
+    }
+}
@@ -0,0 +1,121 @@
+use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{BufferUsages, ShaderType, encase};
+use bevy::render::renderer::RenderQueue;
+use bevy::render::storage::{GpuShaderStorageBuffer, ShaderStorageBuffer};
+
+use crate::prelude::*;
+
+/// Wind state that is the same for every wind affected material, bound by all of them.
+///
+/// Changing a material rebuilds its bind group, so state that changes every frame lives here
+/// instead. The buffer is written in place in the render world, replacing the asset would leave
+/// the bind groups pointing at the old one.
+#[derive(Resource, ExtractResource, Clone)]
+pub struct WindShared {
+    pub buffer: Handle<ShaderStorageBuffer>,
+    pub uniform: WindSharedUniform,
+}
+
+#[derive(ShaderType, Default, Clone, PartialEq, Debug)]
+pub struct WindSharedUniform {
+    pub phases: [WindPhasesUniform; MAX_WIND_PHASES],
+    pub impulses: [WindImpulseUniform; MAX_WIND_IMPULSES],
+    pub interactors: [FoliageInteractorUniform; MAX_FOLIAGE_INTERACTORS],
+    pub trample: TrampleUniform,
+    pub impulse_count: u32,
+    pub interactor_count: u32,
+}
+
+impl WindSharedUniform {
+    pub fn new(
+        phases: &WindPhases,
+        impulses: &WindImpulses,
+        interactors: &FoliageInteractors,
+        trample: &TrampleMap,
+        time: f64,
+    ) -> Self {
+        let mut uniform = Self {
+            trample: trample.uniform(),
+            ..default()
+        };
+
+        for (value, phases) in uniform.phases.iter_mut().zip(phases.values()) {
+            *value = phases;
+        }
+        for (value, impulse) in uniform.impulses.iter_mut().zip(impulses.uniforms(time)) {
+            *value = impulse;
+            uniform.impulse_count += 1;
+        }
+        for (value, interactor) in uniform
+            .interactors
+            .iter_mut()
+            .zip(interactors.values.iter())
+        {
+            *value = *interactor;
+            uniform.interactor_count += 1;
+        }
+
+        uniform
+    }
+}
+
+pub(crate) fn setup_wind_shared(
+    mut commands: Commands,
+    mut buffers: ResMut<Assets<ShaderStorageBuffer>>,
+) {
+    let uniform = WindSharedUniform::default();
+    let mut buffer = ShaderStorageBuffer::from(uniform.clone());
+    buffer.buffer_description.label = Some("wind_shared_buffer");
+    buffer.buffer_description.usage |= BufferUsages::COPY_DST;
+
+    commands.insert_resource(WindShared {
+        buffer: buffers.add(buffer),
+        uniform,
+    });
+}
+
+pub(crate) fn update_wind_shared(
+    mut shared: ResMut<WindShared>,
+    phases: Res<WindPhases>,
+    impulses: Res<WindImpulses>,
+    interactors: Res<FoliageInteractors>,
+    trample: Res<TrampleMap>,
+    clock: Res<WindClock>,
+) {
+    let uniform =
+        WindSharedUniform::new(&phases, &impulses, &interactors, &trample, clock.elapsed());
+    if shared.uniform != uniform {
+        shared.uniform = uniform;
+    }
+}
+
+/// Writes the extracted state into the buffer once it has been prepared.
+pub(crate) fn write_wind_shared(
+    shared: Option<Res<WindShared>>,
+    buffers: Res<RenderAssets<GpuShaderStorageBuffer>>,
+    render_queue: Res<RenderQueue>,
+    mut pending: Local<bool>,
+) {
+    let Some(shared) = shared else {
+        return;
+    };
+    if shared.is_changed() {
+        *pending = true;
+    }
+    if !*pending {
+        return;
+    }
+    let Some(gpu_buffer) = buffers.get(&shared.buffer) else {
+        return;
+    };
+
+    let mut data = encase::StorageBuffer::new(Vec::new());
+    if data.write(&shared.uniform).is_err() {
+        return;
+    }
+
+    render_queue.write_buffer(&gpu_buffer.buffer, 0, data.as_ref());
+    *pending = false;
+}
@@ -0,0 +1,566 @@
+use bevy::image::{ImageAddressMode, ImageSampler, ImageSamplerDescriptor, TextureFormatPixelInfo};
+use std::f64::consts::TAU;
+
+use bevy::asset::LoadState;
+use bevy::platform::collections::{HashMap, HashSet};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use noise::{Fbm, MultiFractal, NoiseFn, OpenSimplex, Perlin, Value, Worley};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::prelude::*;
+
+/// Controls how the [`WindTexture`] is generated, changing it regenerates the texture in the
+/// background.
+///
+/// Each layer is written to its own channel: macro to red, micro to green and the s-curve and
+/// bop phases to blue and alpha.
+#[derive(Resource, Reflect, Debug, Clone, PartialEq)]
+#[reflect(Resource)]
+pub struct WindTextureSettings {
+    pub resolution: u32,
+    /// Falls back to the [`WindSeed`] when `None`.
+    pub seed: Option<u32>,
+    /// Artist supplied noise used instead of the generated layers once loaded, see
+    /// [`prepare_noise_image`] for the requirements.
+    pub asset: Option<Handle<Image>>,
+    pub macro_layer: WindNoiseLayer,
+    pub micro_layer: WindNoiseLayer,
+    pub s_curve_phase_layer: WindNoiseLayer,
+    pub bop_phase_layer: WindNoiseLayer,
+}
+
+impl Default for WindTextureSettings {
+    fn default() -> Self {
+        Self {
+            resolution: 512,
+            seed: None,
+            asset: None,
+            macro_layer: WindNoiseLayer::default(),
+            micro_layer: WindNoiseLayer {
+                seed_offset: 1,
+                ..default()
+            },
+            // Close to one value per texel so neighbouring plants don't move in sync.
+            s_curve_phase_layer: WindNoiseLayer {
+                noise_type: WindNoiseType::Value,
+                frequency: 256.0,
+                seed_offset: 2,
+            },
+            bop_phase_layer: WindNoiseLayer {
+                noise_type: WindNoiseType::Value,
+                frequency: 256.0,
+                seed_offset: 3,
+            },
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub struct WindNoiseLayer {
+    pub noise_type: WindNoiseType,
+    /// Noise periods across the texture.
+    pub frequency: f32,
+    /// Added to the seed to decorrelate layers using the same noise type.
+    pub seed_offset: u32,
+}
+
+impl Default for WindNoiseLayer {
+    fn default() -> Self {
+        Self {
+            noise_type: WindNoiseType::Perlin,
+            frequency: 5.0,
+            seed_offset: 0,
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Clone, PartialEq)]
+pub enum WindNoiseType {
+    Perlin,
+    OpenSimplex,
+    Worley,
+    Value,
+    Fbm {
+        octaves: usize,
+        lacunarity: f32,
+        persistence: f32,
+    },
+}
+
+impl WindNoiseType {
+    pub fn noise_fn(&self, seed: u32) -> Box<dyn NoiseFn<f64, 4>> {
+        match self {
+            Self::Perlin => Box::new(Perlin::new(seed)),
+            Self::OpenSimplex => Box::new(OpenSimplex::new(seed)),
+            Self::Worley => Box::new(Worley::new(seed)),
+            Self::Value => Box::new(Value::new(seed)),
+            Self::Fbm {
+                octaves,
+                lacunarity,
+                persistence,
+            } => Box::new(
+                Fbm::<Perlin>::new(seed)
+                    .set_octaves(*octaves)
+                    .set_lacunarity(*lacunarity as f64)
+                    .set_persistence(*persistence as f64),
+            ),
+        }
+    }
+}
+
+impl WindTextureSettings {
+    pub fn layers(&self) -> [&WindNoiseLayer; 4] {
+        [
+            &self.macro_layer,
+            &self.micro_layer,
+            &self.s_curve_phase_layer,
+            &self.bop_phase_layer,
+        ]
+    }
+
+    pub fn generate(&self, seed: &WindSeed) -> Image {
+        let texture_size = self.resolution.max(1);
+        let mut image_buffer = Vec::with_capacity((texture_size * texture_size * 4) as usize);
+
+        let seed = self.seed.unwrap_or(seed.0);
+        let layers = self.layers().map(|layer| {
+            (
+                layer
+                    .noise_type
+                    .noise_fn(seed.wrapping_add(layer.seed_offset)),
+                layer.frequency as f64,
+            )
+        });
+
+        for y in 0..texture_size {
+            for x in 0..texture_size {
+                for (noise, frequency) in layers.iter() {
+                    let point = torus_point(
+                        x as f64 / texture_size as f64,
+                        y as f64 / texture_size as f64,
+                        *frequency,
+                    );
+
+                    let noise_value = noise.get(point).clamp(-1.0, 1.0);
+
+                    let byte = ((noise_value * 0.5 + 0.5) * 255.0) as u8;
+                    image_buffer.push(byte);
+                }
+            }
+        }
+
+        let mut wind_image = Image::new(
+            Extent3d {
+                width: texture_size,
+                height: texture_size,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            image_buffer,
+            TextureFormat::Rgba8Unorm,
+            default(),
+        );
+
+        generate_mips(&mut wind_image);
+
+        let sampler_descriptor = ImageSampler::Descriptor(ImageSamplerDescriptor {
+            label: Some("Wind Noise Sampler".into()),
+            address_mode_u: ImageAddressMode::Repeat,
+            address_mode_v: ImageAddressMode::Repeat,
+            address_mode_w: ImageAddressMode::Repeat,
+            ..ImageSamplerDescriptor::linear()
+        });
+
+        wind_image.sampler = sampler_descriptor;
+
+        wind_image
+    }
+}
+
+/// Maps uv (0..1) onto a 4D torus, so the noise wraps seamlessly on both axes after `frequency`
+/// periods.
+pub(crate) fn torus_point(u: f64, v: f64, frequency: f64) -> [f64; 4] {
+    let radius = frequency / TAU;
+    let (sin_u, cos_u) = (u * TAU).sin_cos();
+    let (sin_v, cos_v) = (v * TAU).sin_cos();
+
+    [
+        cos_u * radius,
+        sin_u * radius,
+        cos_v * radius,
+        sin_v * radius,
+    ]
+}
+
+/// Appends a box filtered mip chain to a 2D image with 8 bit channels.
+pub(crate) fn generate_mips(image: &mut Image) {
+    let Some(data) = image.data.as_mut() else {
+        return;
+    };
+
+    let channels = image.texture_descriptor.format.pixel_size();
+    let mut width = image.texture_descriptor.size.width as usize;
+    let mut height = image.texture_descriptor.size.height as usize;
+    let mut level_start = 0;
+    let mut mip_level_count = 1;
+
+    while width > 1 || height > 1 {
+        let mip_width = (width / 2).max(1);
+        let mip_height = (height / 2).max(1);
+        let mut mip = Vec::with_capacity(mip_width * mip_height * channels);
+
+        for y in 0..mip_height {
+            for x in 0..mip_width {
+                for channel in 0..channels {
+                    let mut sum = 0u32;
+                    for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                        let sx = (x * 2 + dx).min(width - 1);
+                        let sy = (y * 2 + dy).min(height - 1);
+                        sum += data[level_start + (sy * width + sx) * channels + channel] as u32;
+                    }
+                    mip.push((sum / 4) as u8);
+                }
+            }
+        }
+
+        level_start = data.len();
+        data.extend(mip);
+        width = mip_width;
+        height = mip_height;
+        mip_level_count += 1;
+    }
+
+    image.texture_descriptor.mip_level_count = mip_level_count;
+}
+
+/// How each plant picks its s-curve and bop phase offsets from the noise texture.
+#[derive(Reflect, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WindPhaseSeed {
+    /// Derived from the world position, stable from frame to frame and matched by the
+    /// [`WindSampler`].
+    #[default]
+    Position,
+    /// One texel per instance index. Indices depend on batching, so phases can jump when
+    /// entities are added or removed, and the [`WindSampler`] still uses the position.
+    InstanceIndex,
+}
+
+/// Uses an artist supplied noise texture for this entity instead of the global [`WindTexture`].
+///
+/// The converted image ends up in [`WindAffectedExtension::noise_texture`], entities with the same
+/// mesh, material and noise texture share a prototype. Changes to the source image, e.g. from hot
+/// reloading, are picked up by entities already using it.
+#[derive(Component, Debug, Clone)]
+pub struct WindNoiseTexture(pub Handle<Image>);
+
+#[derive(Debug, Error)]
+pub enum WindTextureError {
+    #[error("Wind noise texture failed to load: {0}")]
+    Load(String),
+    #[error("Wind noise texture doesn't exist")]
+    Missing,
+    #[error("Wind noise texture must be 2D, found {0:?}")]
+    Dimension(TextureDimension),
+    #[error("Wind noise texture needs four 8 bit channels, found {0:?}")]
+    Format(TextureFormat),
+    #[error("Wind noise texture has no data on the CPU")]
+    NoData,
+}
+
+/// Checks an artist supplied noise texture and converts it for the wind shaders.
+///
+/// The image has to be 2D with four 8 bit channels, laid out like the generated texture (macro,
+/// micro and two phase channels). sRGB images are read as linear so the painted values are used
+/// as is. Mips are generated when missing and the sampler is set to repeat.
+pub fn prepare_noise_image(image: &Image) -> Result<Image, WindTextureError> {
+    let descriptor = &image.texture_descriptor;
+    if descriptor.dimension != TextureDimension::D2 || descriptor.size.depth_or_array_layers != 1 {
+        return Err(WindTextureError::Dimension(descriptor.dimension));
+    }
+
+    let format = match descriptor.format {
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => TextureFormat::Rgba8Unorm,
+        format => return Err(WindTextureError::Format(format)),
+    };
+
+    let Some(data) = image.data.as_ref() else {
+        return Err(WindTextureError::NoData);
+    };
+
+    let mut noise_image = image.clone();
+    noise_image.texture_descriptor.format = format;
+    noise_image.texture_view_descriptor = None;
+
+    if descriptor.mip_level_count == 1 {
+        let level_size = (descriptor.size.width * descriptor.size.height * 4) as usize;
+        noise_image.data = Some(data[..level_size.min(data.len())].to_vec());
+        generate_mips(&mut noise_image);
+    }
+
+    noise_image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        label: Some("Wind Noise Sampler".into()),
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        address_mode_w: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::linear()
+    });
+
+    Ok(noise_image)
+}
+
+/// `None` while the image is still loading.
+fn load_noise_image(
+    handle: &Handle<Image>,
+    images: &Assets<Image>,
+    asset_server: &AssetServer,
+) -> Option<Result<Image, WindTextureError>> {
+    if let Some(image) = images.get(handle) {
+        return Some(prepare_noise_image(image));
+    }
+
+    match asset_server.get_load_state(handle) {
+        Some(LoadState::Failed(err)) => Some(Err(WindTextureError::Load(err.to_string()))),
+        Some(_) => None,
+        None => Some(Err(WindTextureError::Missing)),
+    }
+}
+
+/// Converted [`WindNoiseTexture`]s by source image, shared between entities.
+#[derive(Resource, Default)]
+pub(crate) struct PreparedNoiseTextures {
+    values: HashMap<AssetId<Image>, Handle<Image>>,
+}
+
+impl PreparedNoiseTextures {
+    /// Returns the texture to bind for `source`, or `None` while it is still loading.
+    pub(crate) fn resolve(
+        &mut self,
+        source: &Handle<Image>,
+        images: &mut Assets<Image>,
+        asset_server: &AssetServer,
+        fallback: &Handle<Image>,
+    ) -> Option<Handle<Image>> {
+        if let Some(handle) = self.values.get(&source.id()) {
+            return Some(handle.clone());
+        }
+
+        let handle = match load_noise_image(source, images, asset_server)? {
+            Ok(image) => images.add(image),
+            Err(err) => {
+                warn!("{err}, falling back to the generated texture");
+                fallback.clone()
+            }
+        };
+
+        self.values.insert(source.id(), handle.clone());
+        Some(handle)
+    }
+}
+
+/// Converts modified source images again, into the same handle so materials keep using it.
+pub(crate) fn update_prepared_noise_textures(
+    mut events: EventReader<AssetEvent<Image>>,
+    mut prepared: ResMut<PreparedNoiseTextures>,
+    mut images: ResMut<Assets<Image>>,
+    texture: Res<WindTexture>,
+) {
+    let modified = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .filter(|id| prepared.values.contains_key(id))
+        .collect::<HashSet<_>>();
+
+    for source in modified {
+        let Some(handle) = prepared.values.get(&source).cloned() else {
+            continue;
+        };
+
+        // Entries that fell back to the generated texture are dropped, new entities retry them.
+        if handle == texture.0 {
+            prepared.values.remove(&source);
+            continue;
+        }
+
+        let Some(image) = images.get(source) else {
+            continue;
+        };
+        match prepare_noise_image(image) {
+            Ok(image) => {
+                images.insert(&handle, image);
+            }
+            Err(err) => warn!("{err}, keeping the previous noise texture"),
+        }
+    }
+}
+
+/// Sent once a generated or loaded noise image replaced the contents of the [`WindTexture`].
+#[derive(Event, Debug, Clone)]
+pub struct WindTextureReady;
+
+/// Inserts a flat placeholder, the real texture is generated in the background by
+/// [`regenerate_wind_texture`].
+pub(crate) fn setup_wind_texture(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut placeholder = Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[127, 127, 127, 127],
+        TextureFormat::Rgba8Unorm,
+        default(),
+    );
+    placeholder.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        label: Some("Wind Noise Sampler".into()),
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        address_mode_w: ImageAddressMode::Repeat,
+        ..ImageSamplerDescriptor::linear()
+    });
+
+    let handle = images.add(placeholder);
+
+    commands.insert_resource(WindTexture(handle));
+}
+
+fn spawn_generation(settings: &WindTextureSettings, seed: WindSeed) -> Task<Image> {
+    let settings = settings.clone();
+    AsyncComputeTaskPool::get().spawn(async move { settings.generate(&seed) })
+}
+
+pub(crate) fn regenerate_wind_texture(
+    mut images: ResMut<Assets<Image>>,
+    mut events: EventReader<AssetEvent<Image>>,
+    mut ready: EventWriter<WindTextureReady>,
+    asset_server: Res<AssetServer>,
+    texture: Res<WindTexture>,
+    settings: Res<WindTextureSettings>,
+    seed: Res<WindSeed>,
+    mut generated: Local<Option<(WindTextureSettings, WindSeed)>>,
+    mut generation: Local<Option<Task<Image>>>,
+) {
+    if let Some(image) = generation
+        .as_mut()
+        .and_then(|task| block_on(poll_once(task)))
+    {
+        *generation = None;
+        images.insert(&texture.0, image);
+        ready.write(WindTextureReady);
+    }
+
+    let current = (settings.clone(), *seed);
+    let asset_modified = settings
+        .asset
+        .as_ref()
+        .is_some_and(|asset| events.read().any(|event| event.is_modified(asset)));
+
+    if generated.as_ref() == Some(&current) && !asset_modified {
+        return;
+    }
+
+    // Replacing the task drops, and with that cancels, a generation that is still running.
+    let Some(asset) = settings.asset.as_ref() else {
+        *generation = Some(spawn_generation(&settings, *seed));
+        *generated = Some(current);
+        return;
+    };
+
+    // Keep the current texture until the asset has loaded.
+    let Some(image) = load_noise_image(asset, &images, &asset_server) else {
+        return;
+    };
+
+    match image {
+        Ok(image) => {
+            *generation = None;
+            images.insert(&texture.0, image);
+            ready.write(WindTextureReady);
+        }
+        Err(err) => {
+            warn!("{err}, falling back to the generated texture");
+            *generation = Some(spawn_generation(&settings, *seed));
+        }
+    }
+
+    *generated = Some(current);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(width: u32, height: u32, format: TextureFormat, texel: &[u8]) -> Image {
+        Image::new_fill(
+            Extent3d {
+                width,
+                height,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            texel,
+            format,
+            default(),
+        )
+    }
+
+    #[test]
+    fn generate_mips_appends_averaged_levels() {
+        let mut image = image(4, 2, TextureFormat::R8Unorm, &[0]);
+        if let Some(data) = image.data.as_mut() {
+            data.copy_from_slice(&[0, 100, 200, 40, 100, 0, 40, 200]);
+        }
+
+        generate_mips(&mut image);
+
+        assert_eq!(image.texture_descriptor.mip_level_count, 3);
+        assert_eq!(
+            image.data.as_deref(),
+            Some(&[0, 100, 200, 40, 100, 0, 40, 200, 50, 120, 85][..])
+        );
+    }
+
+    #[test]
+    fn prepare_noise_image_reads_srgb_as_linear() {
+        let Ok(prepared) = prepare_noise_image(&image(
+            4,
+            4,
+            TextureFormat::Rgba8UnormSrgb,
+            &[10, 20, 30, 40],
+        )) else {
+            panic!("RGBA8 images are supported");
+        };
+
+        assert_eq!(
+            prepared.texture_descriptor.format,
+            TextureFormat::Rgba8Unorm
+        );
+        assert_eq!(prepared.texture_descriptor.mip_level_count, 3);
+        assert_eq!(
+            prepared.data.as_ref().map(|data| &data[data.len() - 4..]),
+            Some(&[10, 20, 30, 40][..])
+        );
+        assert!(matches!(
+            prepared.sampler,
+            ImageSampler::Descriptor(ImageSamplerDescriptor {
+                address_mode_u: ImageAddressMode::Repeat,
+                address_mode_v: ImageAddressMode::Repeat,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn prepare_noise_image_rejects_other_formats() {
+        assert!(matches!(
+            prepare_noise_image(&image(4, 4, TextureFormat::R8Unorm, &[0])),
+            Err(WindTextureError::Format(TextureFormat::R8Unorm))
+        ));
+    }
+}
@@ -0,0 +1,398 @@
+use bevy::ecs::entity::EntityHashMap;
+use bevy::image::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::prelude::*;
+use bevy::render::extract_resource::ExtractResource;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_resource::{
+    Extent3d, Origin3d, ShaderType, TexelCopyBufferLayout, TexelCopyTextureInfo, TextureAspect,
+    TextureDimension, TextureFormat,
+};
+use bevy::render::renderer::RenderQueue;
+use bevy::render::texture::GpuImage;
+
+use crate::prelude::*;
+
+/// World space map of flattened foliage centred on the camera.
+///
+/// Interactors with a non-zero [`FoliageInteractor::trample`] stamp into it while moving and the
+/// stamps recover over `recovery_time` seconds of [`WindClock`] time.
+#[derive(Resource, Debug, Clone)]
+pub struct TrampleMap {
+    /// Width of the covered square in world units.
+    pub size: f32,
+    /// Texels per side, only read when the texture is created.
+    pub resolution: u32,
+    pub recovery_time: f32,
+    /// Offset of fully trampled blades at the tip.
+    pub strength: f32,
+    origin: Vec2,
+    texels: u32,
+    /// Bend direction on the xz plane and trample amount per texel.
+    values: Vec<Vec3>,
+    dirty: bool,
+}
+
+impl Default for TrampleMap {
+    fn default() -> Self {
+        Self {
+            size: 64.0,
+            resolution: 256,
+            recovery_time: 10.0,
+            strength: 0.5,
+            origin: Vec2::ZERO,
+            texels: 0,
+            values: Vec::new(),
+            dirty: false,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct TrampleTexture(pub Handle<Image>);
+
+/// Texels of the [`TrampleMap`] waiting to be copied into the [`TrampleTexture`].
+///
+/// The texture is written in place, modifying the image would replace it and with that require a
+/// rebuild of every material's bind group.
+#[derive(Resource, ExtractResource, Clone)]
+pub(crate) struct TrampleTexels {
+    image: Handle<Image>,
+    resolution: u32,
+    data: Vec<u8>,
+}
+
+#[derive(ShaderType, Reflect, Default, Clone, Copy, PartialEq, Debug)]
+pub struct TrampleUniform {
+    pub origin: Vec2,
+    pub size: f32,
+    pub strength: f32,
+}
+
+impl TrampleMap {
+    pub fn uniform(&self) -> TrampleUniform {
+        TrampleUniform {
+            origin: self.origin,
+            size: self.size,
+            strength: self.strength,
+        }
+    }
+
+    pub fn texel_size(&self) -> f32 {
+        self.size / self.texels.max(1) as f32
+    }
+
+    fn texel(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / self.texel_size() + self.texels as f32 * 0.5)
+            .floor()
+            .as_ivec2()
+    }
+
+    fn index(&self, texel: IVec2) -> Option<usize> {
+        let resolution = self.texels as i32;
+        if texel.x < 0 || texel.y < 0 || texel.x >= resolution || texel.y >= resolution {
+            return None;
+        }
+
+        Some((texel.y * resolution + texel.x) as usize)
+    }
+
+    /// Moves the map in texel sized steps so it stays centred on `center`, keeping stamps at their
+    /// world position.
+    fn recenter(&mut self, center: Vec2) {
+        let texel_size = self.texel_size();
+        let origin = (center / texel_size).round() * texel_size;
+        let shift = ((origin - self.origin) / texel_size).round().as_ivec2();
+        if shift == IVec2::ZERO {
+            return;
+        }
+
+        self.origin = origin;
+        if !self.values.iter().any(|value| value.z > 0.0) {
+            return;
+        }
+
+        let resolution = self.texels as i32;
+        let mut values = vec![Vec3::ZERO; self.values.len()];
+        for y in 0..resolution {
+            for x in 0..resolution {
+                if let Some(index) = self.index(IVec2::new(x, y) + shift) {
+                    values[(y * resolution + x) as usize] = self.values[index];
+                }
+            }
+        }
+
+        self.values = values;
+        self.dirty = true;
+    }
+
+    fn decay(&mut self, delta: f32) {
+        let recovery = delta / self.recovery_time.max(0.0001);
+
+        for value in self.values.iter_mut().filter(|value| value.z > 0.0) {
+            value.z = (value.z - recovery).max(0.0);
+            self.dirty = true;
+        }
+    }
+
+    /// Flattens a disc of `radius` around `position`, bending blades along `direction`.
+    pub fn stamp(&mut self, position: Vec2, radius: f32, amount: f32, direction: Vec2) {
+        let texel_size = self.texel_size();
+        let center = self.texel(position);
+        let extent = (radius / texel_size).ceil() as i32;
+
+        for y in -extent..=extent {
+            for x in -extent..=extent {
+                let texel = center + IVec2::new(x, y);
+                let Some(index) = self.index(texel) else {
+                    continue;
+                };
+
+                let distance = IVec2::new(x, y).as_vec2().length() * texel_size;
+                let weight = 1.0 - (distance / radius.max(0.0001)).clamp(0.0, 1.0);
+                let value = amount * weight;
+
+                if value > self.values[index].z {
+                    self.values[index] = direction.extend(value.min(1.0));
+                    self.dirty = true;
+                }
+            }
+        }
+    }
+
+    /// Matches `calculate_trample_displacement` in `wind_displace.wgsl`, without filtering.
+    pub fn displacement(&self, position: Vec3) -> Vec3 {
+        let Some(value) = self
+            .index(self.texel(position.xz()))
+            .and_then(|index| self.values.get(index))
+        else {
+            return Vec3::ZERO;
+        };
+
+        Vec3::new(value.x, -1.0, value.y) * value.z * self.strength
+    }
+
+    fn write_texels(&self, data: &mut Vec<u8>) {
+        data.clear();
+        for value in self.values.iter() {
+            data.push(((value.x * 0.5 + 0.5) * 255.0) as u8);
+            data.push(((value.y * 0.5 + 0.5) * 255.0) as u8);
+            data.push((value.z * 255.0) as u8);
+            data.push(255);
+        }
+    }
+}
+
+pub(crate) fn setup_trample_texture(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    mut trample: ResMut<TrampleMap>,
+) {
+    let resolution = trample.resolution.max(1);
+    trample.texels = resolution;
+    trample.values = vec![Vec3::ZERO; (resolution * resolution) as usize];
+
+    let mut trample_image = Image::new_fill(
+        Extent3d {
+            width: resolution,
+            height: resolution,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[127, 127, 0, 255],
+        TextureFormat::Rgba8Unorm,
+        default(),
+    );
+
+    trample_image.sampler = ImageSampler::Descriptor(ImageSamplerDescriptor {
+        label: Some("Wind Trample Sampler".into()),
+        address_mode_u: ImageAddressMode::ClampToEdge,
+        address_mode_v: ImageAddressMode::ClampToEdge,
+        address_mode_w: ImageAddressMode::ClampToEdge,
+        mag_filter: ImageFilterMode::Linear,
+        min_filter: ImageFilterMode::Linear,
+        ..default()
+    });
+
+    let handle = images.add(trample_image);
+
+    commands.insert_resource(TrampleTexels {
+        image: handle.clone(),
+        resolution,
+        data: Vec::new(),
+    });
+    commands.insert_resource(TrampleTexture(handle));
+}
+
+pub(crate) fn update_trample_map(
+    clock: Res<WindClock>,
+    mut trample: ResMut<TrampleMap>,
+    mut texels: ResMut<TrampleTexels>,
+    q: Query<(Entity, &FoliageInteractor, &GlobalTransform)>,
+    camera: Query<&GlobalTransform, With<Camera3d>>,
+    mut previous: Local<EntityHashMap<Vec3>>,
+    mut decayed: Local<Option<f64>>,
+) {
+    let uniform = trample.uniform();
+    let map = trample.bypass_change_detection();
+
+    if let Some(camera) = camera.iter().next() {
+        map.recenter(camera.translation().xz());
+    }
+
+    // Setting the clock back doesn't bring stamps back.
+    let delta = decayed.map_or(0.0, |decayed| (clock.elapsed() - decayed).max(0.0));
+    map.decay(delta as f32);
+    *decayed = Some(clock.elapsed());
+
+    let mut current = EntityHashMap::default();
+    for (entity, interactor, transform) in q.iter() {
+        let position = transform.translation();
+        current.insert(entity, position);
+
+        if interactor.trample <= 0.0 {
+            continue;
+        }
+        let Some(last) = previous.get(&entity) else {
+            continue;
+        };
+
+        let movement = (position - *last).xz();
+        if movement.length() > 0.001 {
+            map.stamp(
+                position.xz(),
+                interactor.radius,
+                interactor.trample,
+                movement.normalize(),
+            );
+        }
+    }
+    *previous = current;
+
+    if map.dirty {
+        map.dirty = false;
+        map.write_texels(&mut texels.data);
+    }
+
+    if map.uniform() != uniform {
+        trample.set_changed();
+    }
+}
+
+/// Copies changed texels into the texture once it has been prepared.
+pub(crate) fn write_trample_texture(
+    texels: Option<Res<TrampleTexels>>,
+    images: Res<RenderAssets<GpuImage>>,
+    render_queue: Res<RenderQueue>,
+    mut pending: Local<bool>,
+) {
+    let Some(texels) = texels else {
+        return;
+    };
+    if texels.is_changed() {
+        *pending = !texels.data.is_empty();
+    }
+    if !*pending {
+        return;
+    }
+    let Some(gpu_image) = images.get(&texels.image) else {
+        return;
+    };
+
+    render_queue.write_texture(
+        TexelCopyTextureInfo {
+            texture: &gpu_image.texture,
+            mip_level: 0,
+            origin: Origin3d::ZERO,
+            aspect: TextureAspect::All,
+        },
+        &texels.data,
+        TexelCopyBufferLayout {
+            offset: 0,
+            bytes_per_row: Some(texels.resolution * 4),
+            rows_per_image: None,
+        },
+        Extent3d {
+            width: texels.resolution,
+            height: texels.resolution,
+            depth_or_array_layers: 1,
+        },
+    );
+    *pending = false;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// An 8 by 8 map with one world unit per texel.
+    fn map() -> TrampleMap {
+        TrampleMap {
+            size: 8.0,
+            texels: 8,
+            values: vec![Vec3::ZERO; 64],
+            ..default()
+        }
+    }
+
+    #[test]
+    fn stamp_bends_along_direction() {
+        let mut trample = map();
+        trample.stamp(Vec2::new(0.5, 0.5), 1.0, 1.0, Vec2::X);
+
+        assert_eq!(
+            trample.displacement(Vec3::new(0.5, 0.0, 0.5)),
+            Vec3::new(1.0, -1.0, 0.0) * trample.strength
+        );
+        assert_eq!(trample.displacement(Vec3::new(3.5, 0.0, 3.5)), Vec3::ZERO);
+    }
+
+    #[test]
+    fn stamp_keeps_the_stronger_value() {
+        let mut trample = map();
+        trample.stamp(Vec2::new(0.5, 0.5), 1.0, 1.0, Vec2::X);
+        trample.stamp(Vec2::new(0.5, 0.5), 1.0, 0.5, Vec2::Y);
+
+        assert_eq!(
+            trample.displacement(Vec3::new(0.5, 0.0, 0.5)),
+            Vec3::new(1.0, -1.0, 0.0) * trample.strength
+        );
+    }
+
+    #[test]
+    fn recenter_keeps_stamps_in_place() {
+        let mut trample = map();
+        trample.stamp(Vec2::new(0.5, 0.5), 1.0, 1.0, Vec2::X);
+        let displacement = trample.displacement(Vec3::new(0.5, 0.0, 0.5));
+
+        trample.recenter(Vec2::new(2.2, -1.1));
+
+        assert_eq!(trample.uniform().origin, Vec2::new(2.0, -1.0));
+        assert_eq!(trample.displacement(Vec3::new(0.5, 0.0, 0.5)), displacement);
+    }
+
+    #[test]
+    fn recenter_drops_stamps_left_behind() {
+        let mut trample = map();
+        trample.stamp(Vec2::new(0.5, 0.5), 1.0, 1.0, Vec2::X);
+
+        trample.recenter(Vec2::new(20.0, 0.0));
+        trample.recenter(Vec2::ZERO);
+
+        assert!(trample.values.iter().all(|value| value.z == 0.0));
+    }
+
+    #[test]
+    fn decay_recovers_over_recovery_time() {
+        let mut trample = map();
+        trample.stamp(Vec2::new(0.5, 0.5), 1.0, 1.0, Vec2::X);
+
+        trample.decay(trample.recovery_time * 0.5);
+        assert_eq!(
+            trample.displacement(Vec3::new(0.5, 0.0, 0.5)),
+            Vec3::new(0.5, -0.5, 0.0) * trample.strength
+        );
+
+        trample.decay(trample.recovery_time);
+        assert_eq!(trample.displacement(Vec3::new(0.5, 0.0, 0.5)), Vec3::ZERO);
+    }
+}
@@ -0,0 +1,212 @@
+use bevy::math::curve::{Curve, EaseFunction};
+use bevy::prelude::*;
+
+use crate::prelude::*;
+
+/// Blends [`Wind`] towards `target` over `duration` seconds of [`WindClock`] time once inserted as
+/// a resource.
+///
+/// Starts at the clock time of the first update after insertion, for lockstep or replays choose it
+/// with [`WindTransition::starting_at`].
+#[derive(Resource, Debug, Clone)]
+pub struct WindTransition {
+    pub target: Wind,
+    pub duration: f32,
+    pub easing: EaseFunction,
+    /// Progress (0..1) at which boolean fields switch to the target.
+    pub switch_at: f32,
+    start: Option<f64>,
+    elapsed: f32,
+    from: Option<Wind>,
+    finished: bool,
+}
+
+impl WindTransition {
+    pub fn new(target: Wind, duration: f32) -> Self {
+        Self {
+            target,
+            duration,
+            easing: EaseFunction::SmoothStep,
+            switch_at: 0.5,
+            start: None,
+            elapsed: 0.0,
+            from: None,
+            finished: false,
+        }
+    }
+
+    pub fn starting_at(mut self, start: f64) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn with_easing(mut self, easing: EaseFunction) -> Self {
+        self.easing = easing;
+        self
+    }
+
+    pub fn with_switch_at(mut self, switch_at: f32) -> Self {
+        self.switch_at = switch_at;
+        self
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress_at(self.elapsed)
+    }
+
+    fn progress_at(&self, elapsed: f32) -> f32 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+
+        (elapsed / self.duration).clamp(0.0, 1.0)
+    }
+
+    /// The blended wind at [`WindClock`] time `time`, `None` before the transition has started.
+    pub fn sample(&self, time: f64) -> Option<Wind> {
+        let (Some(start), Some(from)) = (self.start, self.from.as_ref()) else {
+            return None;
+        };
+
+        let progress = self.progress_at((time - start) as f32);
+        if progress >= 1.0 {
+            return Some(self.target.clone());
+        }
+
+        let t = self.easing.sample_clamped(progress);
+        Some(from.interpolate(&self.target, t, progress >= self.switch_at))
+    }
+}
+
+#[derive(Event, Debug, Clone)]
+pub struct WindTransitionFinished {
+    pub wind: Wind,
+}
+
+impl Wind {
+    pub fn interpolate(&self, target: &Wind, t: f32, switch: bool) -> Wind {
+        let lerp = |a: f32, b: f32| a + (b - a) * t;
+        let switched = if switch { target } else { self };
+
+        Wind {
+            direction: self
+                .direction
+                .lerp(target.direction, t)
+                .normalize_or(target.direction),
+            vertical: lerp(self.vertical, target.vertical),
+            strength: lerp(self.strength, target.strength),
+            noise_scale: lerp(self.noise_scale, target.noise_scale),
+            scroll_speed: lerp(self.scroll_speed, target.scroll_speed),
+            bend_exponent: lerp(self.bend_exponent, target.bend_exponent),
+            round_exponent: lerp(self.round_exponent, target.round_exponent),
+            micro_strength: lerp(self.micro_strength, target.micro_strength),
+            micro_noise_scale: lerp(self.micro_noise_scale, target.micro_noise_scale),
+            micro_scroll_speed: lerp(self.micro_scroll_speed, target.micro_scroll_speed),
+            s_curve_speed: lerp(self.s_curve_speed, target.s_curve_speed),
+            s_curve_strength: lerp(self.s_curve_strength, target.s_curve_strength),
+            s_curve_frequency: lerp(self.s_curve_frequency, target.s_curve_frequency),
+            bop_speed: lerp(self.bop_speed, target.bop_speed),
+            bop_strength: lerp(self.bop_strength, target.bop_strength),
+            twist_strength: lerp(self.twist_strength, target.twist_strength),
+            enable_billboarding: switched.enable_billboarding,
+            enable_edge_correction: switched.enable_edge_correction,
+            edge_correction_factor: lerp(
+                self.edge_correction_factor,
+                target.edge_correction_factor,
+            ),
+            lod_threshold: lerp(self.lod_threshold, target.lod_threshold),
+            enable_flow_field: switched.enable_flow_field,
+            flow_scale: lerp(self.flow_scale, target.flow_scale),
+            flow_scroll_speed: lerp(self.flow_scroll_speed, target.flow_scroll_speed),
+            enable_noise_volume: switched.enable_noise_volume,
+            volume_speed: lerp(self.volume_speed, target.volume_speed),
+            phase_seed: switched.phase_seed,
+        }
+    }
+}
+
+/// Runs after the [`WindClock`] has been advanced for the frame.
+///
+/// Finished transitions are kept for one more frame, so the phases still see the blend for the
+/// clock time between the last two frames.
+pub(crate) fn update_wind_transition(
+    mut cmd: Commands,
+    clock: Res<WindClock>,
+    transition: Option<ResMut<WindTransition>>,
+    mut wind: ResMut<Wind>,
+    mut events: EventWriter<WindTransitionFinished>,
+) {
+    let Some(mut transition) = transition else {
+        return;
+    };
+
+    if transition.finished {
+        cmd.remove_resource::<WindTransition>();
+        return;
+    }
+
+    let start = *transition.start.get_or_insert(clock.elapsed());
+    transition.from.get_or_insert_with(|| wind.clone());
+    transition.elapsed = (clock.elapsed() - start) as f32;
+
+    if let Some(blended) = transition.sample(clock.elapsed()) {
+        *wind = blended;
+    }
+
+    if transition.progress() >= 1.0 {
+        transition.finished = true;
+        events.write(WindTransitionFinished { wind: wind.clone() });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn winds() -> (Wind, Wind) {
+        let from = Wind {
+            direction: Vec2::X,
+            strength: 1.0,
+            enable_flow_field: false,
+            ..default()
+        };
+        let target = Wind {
+            direction: Vec2::Y,
+            strength: 3.0,
+            enable_flow_field: true,
+            ..default()
+        };
+        (from, target)
+    }
+
+    #[test]
+    fn interpolate_blends_numbers_and_directions() {
+        let (from, target) = winds();
+        let wind = from.interpolate(&target, 0.5, false);
+
+        assert_eq!(wind.strength, 2.0);
+        assert!(wind.direction.abs_diff_eq(Vec2::ONE.normalize(), 0.0001));
+    }
+
+    #[test]
+    fn interpolate_switches_flags_on_request() {
+        let (from, target) = winds();
+
+        assert!(!from.interpolate(&target, 0.9, false).enable_flow_field);
+        assert!(from.interpolate(&target, 0.1, true).enable_flow_field);
+    }
+
+    #[test]
+    fn opposite_directions_fall_back_to_target() {
+        let (from, target) = winds();
+        let target = Wind {
+            direction: -from.direction,
+            ..target
+        };
+
+        assert_eq!(
+            from.interpolate(&target, 0.5, false).direction,
+            target.direction
+        );
+    }
+}
@@ -0,0 +1,123 @@
+use bevy::{
+    pbr::{ExtendedMaterial, MaterialPipeline, MaterialPipelineKey},
+    prelude::*,
+    render::{
+        mesh::MeshVertexBufferLayoutRef,
+        render_asset::RenderAssets,
+        render_resource::{
+            AsBindGroup, AsBindGroupShaderType, RenderPipelineDescriptor, ShaderRef, ShaderType,
+            SpecializedMeshPipelineError,
+        },
+        texture::GpuImage,
+    },
+};
+
+use crate::{WindPlugin, prelude::*};
+
+/// Wind for entities using an [`UnlitFoliageMaterial`].
+///
+/// Unlit foliage neither casts shadows nor writes to the prepass.
+pub struct UnlitMaterialWindPlugin;
+
+impl Plugin for UnlitMaterialWindPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<UnlitFoliageMaterial>()
+            .add_plugins(MaterialPlugin::<UnlitFoliageMaterial>::default())
+            .add_plugins(MaterialPlugin::<WindAffectedUnlitMaterial> {
+                prepass_enabled: false,
+                shadows_enabled: false,
+                ..default()
+            })
+            .add_plugins(WindPlugin::<UnlitFoliageMaterial, WindAffectedUnlitMaterial>::default());
+    }
+}
+
+pub type WindAffectedUnlitMaterial = ExtendedMaterial<UnlitFoliageMaterial, WindAffectedExtension>;
+
+const SHADER_UNLIT_ASSET_PATH: &str = "shaders/unlit_foliage.wgsl";
+
+/// Flat shaded, double sided material for stylized or distant foliage.
+#[derive(Asset, Reflect, AsBindGroup, Debug, Clone)]
+#[uniform(0, UnlitFoliageUniform)]
+pub struct UnlitFoliageMaterial {
+    pub base_color: Color,
+    #[texture(1)]
+    #[sampler(2)]
+    pub base_color_texture: Option<Handle<Image>>,
+    pub alpha_mode: AlphaMode,
+}
+
+impl Default for UnlitFoliageMaterial {
+    fn default() -> Self {
+        Self {
+            base_color: Color::WHITE,
+            base_color_texture: None,
+            alpha_mode: AlphaMode::Opaque,
+        }
+    }
+}
+
+impl From<Color> for UnlitFoliageMaterial {
+    fn from(base_color: Color) -> Self {
+        Self {
+            base_color,
+            ..default()
+        }
+    }
+}
+
+#[derive(ShaderType, Clone)]
+pub struct UnlitFoliageUniform {
+    pub base_color: Vec4,
+    /// Fragments with a lower alpha are discarded, only set for [`AlphaMode::Mask`].
+    pub alpha_cutoff: f32,
+}
+
+impl AsBindGroupShaderType<UnlitFoliageUniform> for UnlitFoliageMaterial {
+    fn as_bind_group_shader_type(&self, _images: &RenderAssets<GpuImage>) -> UnlitFoliageUniform {
+        UnlitFoliageUniform {
+            base_color: LinearRgba::from(self.base_color).to_vec4(),
+            alpha_cutoff: match self.alpha_mode {
+                AlphaMode::Mask(cutoff) => cutoff,
+                _ => 0.0,
+            },
+        }
+    }
+}
+
+impl Material for UnlitFoliageMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_UNLIT_ASSET_PATH.into()
+    }
+
+    fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    fn specialize(
+        _pipeline: &MaterialPipeline<Self>,
+        descriptor: &mut RenderPipelineDescriptor,
+        _layout: &MeshVertexBufferLayoutRef,
+        _key: MaterialPipelineKey<Self>,
+    ) -> Result<(), SpecializedMeshPipelineError> {
+        descriptor.primitive.cull_mode = None;
+        Ok(())
+    }
+}
+
+impl WindAffectable<UnlitFoliageMaterial, WindAffectedUnlitMaterial> for WindAffectedUnlitMaterial {
+    fn create_material(
+        base: UnlitFoliageMaterial,
+        extension: WindAffectedExtension,
+    ) -> WindAffectedUnlitMaterial {
+        ExtendedMaterial { base, extension }
+    }
+
+    fn extension(material: &WindAffectedUnlitMaterial) -> &WindAffectedExtension {
+        &material.extension
+    }
+
+    fn extension_mut(material: &mut WindAffectedUnlitMaterial) -> &mut WindAffectedExtension {
+        &mut material.extension
+    }
+}
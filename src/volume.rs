@@ -0,0 +1,149 @@
+use std::f64::consts::TAU;
+
+use bevy::image::{ImageAddressMode, ImageFilterMode, ImageSampler, ImageSamplerDescriptor};
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+use bevy::tasks::{AsyncComputeTaskPool, Task, block_on, poll_once};
+use noise::{NoiseFn, Perlin};
+
+use crate::prelude::*;
+
+/// Macro noise evolving over time, sampled instead of the red channel of the [`WindTexture`] when
+/// `Wind::enable_noise_volume` is set.
+///
+/// Width and height cover the same area as the noise texture, depth is time and loops seamlessly
+/// so the shaders can walk through it with `Wind::volume_speed`. Generated in the background at
+/// startup, and again when the [`WindSeed`] changes, unless inserted beforehand, e.g. from a 3D R8
+/// image. Flat until the first one is done.
+#[derive(Resource)]
+pub struct WindNoiseVolume(pub Handle<Image>);
+
+/// Seed of the last generation started, absent when the volume was inserted by the user.
+#[derive(Resource)]
+pub(crate) struct GeneratedWindNoiseVolume(Option<WindSeed>);
+
+pub(crate) fn setup_wind_noise_volume(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    noise_volume: Option<Res<WindNoiseVolume>>,
+) {
+    if noise_volume.is_some() {
+        return;
+    }
+
+    let mut placeholder = Image::new_fill(
+        Extent3d {
+            width: 1,
+            height: 1,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D3,
+        &[127],
+        TextureFormat::R8Unorm,
+        default(),
+    );
+    placeholder.sampler = volume_sampler();
+
+    commands.insert_resource(WindNoiseVolume(images.add(placeholder)));
+    commands.insert_resource(GeneratedWindNoiseVolume(None));
+}
+
+pub(crate) fn regenerate_wind_noise_volume(
+    mut images: ResMut<Assets<Image>>,
+    noise_volume: Res<WindNoiseVolume>,
+    generated: Option<ResMut<GeneratedWindNoiseVolume>>,
+    seed: Res<WindSeed>,
+    mut generation: Local<Option<Task<Image>>>,
+) {
+    let Some(mut generated) = generated else {
+        return;
+    };
+
+    if let Some(image) = generation
+        .as_mut()
+        .and_then(|task| block_on(poll_once(task)))
+    {
+        *generation = None;
+        images.insert(&noise_volume.0, image);
+    }
+
+    if generated.0 == Some(*seed) {
+        return;
+    }
+
+    // Replacing the task drops, and with that cancels, a generation that is still running.
+    let seed = *seed;
+    *generation =
+        Some(AsyncComputeTaskPool::get().spawn(async move { generate_volume_image(seed) }));
+    generated.0 = Some(seed);
+}
+
+fn generate_volume_image(seed: WindSeed) -> Image {
+    let texture_size = 64;
+    let depth = 32;
+    let frequency = 5.0;
+    // Distance travelled through the noise over one loop, larger values change the shapes faster.
+    let evolution = 2.0;
+    let mut image_buffer = Vec::with_capacity((texture_size * texture_size * depth) as usize);
+
+    // Offset so the volume doesn't line up with the noise and flow textures.
+    let perlin = Perlin::new(seed.0.wrapping_add(4));
+    let radius = frequency / TAU;
+
+    for z in 0..depth {
+        // Blends towards the noise one loop behind, which is where the first slice starts again.
+        let w = z as f64 / depth as f64;
+        let ahead = w * evolution;
+        let behind = (w - 1.0) * evolution;
+        let normalization = ((1.0 - w).powi(2) + w.powi(2)).sqrt();
+
+        for y in 0..texture_size {
+            for x in 0..texture_size {
+                let (sin_u, cos_u) = (x as f64 / texture_size as f64 * TAU).sin_cos();
+                let (sin_v, cos_v) = (y as f64 / texture_size as f64 * TAU).sin_cos();
+                let point = |offset: f64| {
+                    [
+                        cos_u * radius + offset,
+                        sin_u * radius + offset,
+                        cos_v * radius + offset,
+                        sin_v * radius + offset,
+                    ]
+                };
+
+                let noise_value = ((1.0 - w) * perlin.get(point(ahead))
+                    + w * perlin.get(point(behind)))
+                    / normalization;
+
+                image_buffer.push(((noise_value.clamp(-1.0, 1.0) * 0.5 + 0.5) * 255.0) as u8);
+            }
+        }
+    }
+
+    let mut volume_image = Image::new(
+        Extent3d {
+            width: texture_size,
+            height: texture_size,
+            depth_or_array_layers: depth,
+        },
+        TextureDimension::D3,
+        image_buffer,
+        TextureFormat::R8Unorm,
+        default(),
+    );
+
+    volume_image.sampler = volume_sampler();
+
+    volume_image
+}
+
+fn volume_sampler() -> ImageSampler {
+    ImageSampler::Descriptor(ImageSamplerDescriptor {
+        label: Some("Wind Noise Volume Sampler".into()),
+        address_mode_u: ImageAddressMode::Repeat,
+        address_mode_v: ImageAddressMode::Repeat,
+        address_mode_w: ImageAddressMode::Repeat,
+        mag_filter: ImageFilterMode::Linear,
+        min_filter: ImageFilterMode::Linear,
+        ..default()
+    })
+}
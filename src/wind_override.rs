@@ -0,0 +1,145 @@
+use bevy::prelude::*;
+
+use crate::prelude::*;
+
+/// Replaces individual fields of the global [`Wind`] for an entity or prototype.
+#[derive(Component, Reflect, Debug, Clone, Default, PartialEq)]
+#[reflect(Component)]
+pub struct WindOverride {
+    pub direction: Option<Vec2>,
+    pub vertical: Option<f32>,
+    pub strength: Option<f32>,
+    pub noise_scale: Option<f32>,
+    pub scroll_speed: Option<f32>,
+    pub bend_exponent: Option<f32>,
+    pub round_exponent: Option<f32>,
+    pub micro_strength: Option<f32>,
+    pub micro_noise_scale: Option<f32>,
+    pub micro_scroll_speed: Option<f32>,
+    pub s_curve_speed: Option<f32>,
+    pub s_curve_strength: Option<f32>,
+    pub s_curve_frequency: Option<f32>,
+    pub bop_speed: Option<f32>,
+    pub bop_strength: Option<f32>,
+    pub twist_strength: Option<f32>,
+    pub enable_billboarding: Option<bool>,
+    pub enable_edge_correction: Option<bool>,
+    pub edge_correction_factor: Option<f32>,
+    pub lod_threshold: Option<f32>,
+    pub enable_flow_field: Option<bool>,
+    pub flow_scale: Option<f32>,
+    pub flow_scroll_speed: Option<f32>,
+    pub enable_noise_volume: Option<bool>,
+    pub volume_speed: Option<f32>,
+    pub phase_seed: Option<WindPhaseSeed>,
+}
+
+impl From<Wind> for WindOverride {
+    fn from(wind: Wind) -> Self {
+        Self {
+            direction: Some(wind.direction),
+            vertical: Some(wind.vertical),
+            strength: Some(wind.strength),
+            noise_scale: Some(wind.noise_scale),
+            scroll_speed: Some(wind.scroll_speed),
+            bend_exponent: Some(wind.bend_exponent),
+            round_exponent: Some(wind.round_exponent),
+            micro_strength: Some(wind.micro_strength),
+            micro_noise_scale: Some(wind.micro_noise_scale),
+            micro_scroll_speed: Some(wind.micro_scroll_speed),
+            s_curve_speed: Some(wind.s_curve_speed),
+            s_curve_strength: Some(wind.s_curve_strength),
+            s_curve_frequency: Some(wind.s_curve_frequency),
+            bop_speed: Some(wind.bop_speed),
+            bop_strength: Some(wind.bop_strength),
+            twist_strength: Some(wind.twist_strength),
+            enable_billboarding: Some(wind.enable_billboarding),
+            enable_edge_correction: Some(wind.enable_edge_correction),
+            edge_correction_factor: Some(wind.edge_correction_factor),
+            lod_threshold: Some(wind.lod_threshold),
+            enable_flow_field: Some(wind.enable_flow_field),
+            flow_scale: Some(wind.flow_scale),
+            flow_scroll_speed: Some(wind.flow_scroll_speed),
+            enable_noise_volume: Some(wind.enable_noise_volume),
+            volume_speed: Some(wind.volume_speed),
+            phase_seed: Some(wind.phase_seed),
+        }
+    }
+}
+
+impl WindOverride {
+    pub fn apply(&self, wind: &Wind) -> Wind {
+        Wind {
+            direction: self.direction.unwrap_or(wind.direction),
+            vertical: self.vertical.unwrap_or(wind.vertical),
+            strength: self.strength.unwrap_or(wind.strength),
+            noise_scale: self.noise_scale.unwrap_or(wind.noise_scale),
+            scroll_speed: self.scroll_speed.unwrap_or(wind.scroll_speed),
+            bend_exponent: self.bend_exponent.unwrap_or(wind.bend_exponent),
+            round_exponent: self.round_exponent.unwrap_or(wind.round_exponent),
+            micro_strength: self.micro_strength.unwrap_or(wind.micro_strength),
+            micro_noise_scale: self.micro_noise_scale.unwrap_or(wind.micro_noise_scale),
+            micro_scroll_speed: self.micro_scroll_speed.unwrap_or(wind.micro_scroll_speed),
+            s_curve_speed: self.s_curve_speed.unwrap_or(wind.s_curve_speed),
+            s_curve_strength: self.s_curve_strength.unwrap_or(wind.s_curve_strength),
+            s_curve_frequency: self.s_curve_frequency.unwrap_or(wind.s_curve_frequency),
+            bop_speed: self.bop_speed.unwrap_or(wind.bop_speed),
+            bop_strength: self.bop_strength.unwrap_or(wind.bop_strength),
+            twist_strength: self.twist_strength.unwrap_or(wind.twist_strength),
+            enable_billboarding: self.enable_billboarding.unwrap_or(wind.enable_billboarding),
+            enable_edge_correction: self
+                .enable_edge_correction
+                .unwrap_or(wind.enable_edge_correction),
+            edge_correction_factor: self
+                .edge_correction_factor
+                .unwrap_or(wind.edge_correction_factor),
+            lod_threshold: self.lod_threshold.unwrap_or(wind.lod_threshold),
+            enable_flow_field: self.enable_flow_field.unwrap_or(wind.enable_flow_field),
+            flow_scale: self.flow_scale.unwrap_or(wind.flow_scale),
+            flow_scroll_speed: self.flow_scroll_speed.unwrap_or(wind.flow_scroll_speed),
+            enable_noise_volume: self.enable_noise_volume.unwrap_or(wind.enable_noise_volume),
+            volume_speed: self.volume_speed.unwrap_or(wind.volume_speed),
+            phase_seed: self.phase_seed.unwrap_or(wind.phase_seed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn apply_replaces_only_set_fields() {
+        let wind = Wind::default();
+        let wind_override = WindOverride {
+            strength: Some(3.0),
+            enable_flow_field: Some(!wind.enable_flow_field),
+            ..default()
+        };
+
+        assert_eq!(
+            WindOverride::apply(&wind_override, &wind),
+            Wind {
+                strength: 3.0,
+                enable_flow_field: !wind.enable_flow_field,
+                ..wind.clone()
+            }
+        );
+        assert_eq!(WindOverride::default().apply(&wind), wind);
+    }
+
+    #[test]
+    fn full_override_replaces_everything() {
+        let target = Wind {
+            strength: 0.25,
+            direction: Vec2::NEG_X,
+            phase_seed: WindPhaseSeed::InstanceIndex,
+            ..default()
+        };
+
+        assert_eq!(
+            WindOverride::from(target.clone()).apply(&Wind::default()),
+            target
+        );
+    }
+}
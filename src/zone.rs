@@ -0,0 +1,266 @@
+use bevy::prelude::*;
+use bevy::render::render_resource::ShaderType;
+
+use crate::prelude::*;
+
+pub const MAX_WIND_ZONES: usize = 8;
+
+/// Lower bound for zone extents, the shader divides by them.
+const MIN_ZONE_EXTENT: f32 = 0.0001;
+/// Upper bound for the cone angle, its tangent is infinite at 90°.
+const MAX_ZONE_CONE_ANGLE: f32 = std::f32::consts::FRAC_PI_2 - 0.001;
+
+#[derive(Component, Reflect, Debug, Clone)]
+#[reflect(Component)]
+#[require(Transform)]
+pub struct WindZone {
+    pub shape: WindZoneShape,
+    /// Fraction of the shape (0..1) with full influence, fading out towards the boundary.
+    pub inner: f32,
+    /// Multiplier for the wind strength inside the zone.
+    pub strength: f32,
+    /// Direction on the local xz plane, `None` keeps the global direction.
+    pub direction: Option<Vec2>,
+}
+
+impl Default for WindZone {
+    fn default() -> Self {
+        Self {
+            shape: WindZoneShape::default(),
+            inner: 0.5,
+            strength: 1.0,
+            direction: None,
+        }
+    }
+}
+
+#[derive(Reflect, Debug, Clone, Copy)]
+pub enum WindZoneShape {
+    Sphere {
+        radius: f32,
+    },
+    Box {
+        half_extents: Vec3,
+    },
+    /// Cone with its apex at the origin, opening along the local forward axis.
+    Cone {
+        angle: f32,
+        length: f32,
+    },
+}
+
+impl Default for WindZoneShape {
+    fn default() -> Self {
+        Self::Sphere { radius: 5.0 }
+    }
+}
+
+#[derive(Resource, Default, Clone, PartialEq, Debug)]
+pub struct WindZones {
+    pub values: Vec<WindZoneUniform>,
+}
+
+#[derive(ShaderType, Reflect, Default, Clone, Copy, PartialEq, Debug)]
+pub struct WindZoneUniform {
+    pub zone_from_world: Mat4,
+    pub extents: Vec4,
+    pub direction: Vec2,
+    pub direction_weight: f32,
+    pub shape: u32,
+    pub inner: f32,
+    pub strength: f32,
+}
+
+impl WindZoneUniform {
+    pub fn new(zone: &WindZone, transform: &GlobalTransform) -> Self {
+        let (shape, extents) = match zone.shape {
+            WindZoneShape::Sphere { radius } => {
+                (0, Vec4::new(radius.max(MIN_ZONE_EXTENT), 0.0, 0.0, 0.0))
+            }
+            WindZoneShape::Box { half_extents } => (
+                1,
+                half_extents.max(Vec3::splat(MIN_ZONE_EXTENT)).extend(0.0),
+            ),
+            WindZoneShape::Cone { angle, length } => (
+                2,
+                Vec4::new(
+                    length.max(MIN_ZONE_EXTENT),
+                    angle.clamp(0.0, MAX_ZONE_CONE_ANGLE).tan(),
+                    0.0,
+                    0.0,
+                ),
+            ),
+        };
+
+        let (direction, direction_weight) = match zone.direction {
+            Some(direction) => {
+                let world = transform.rotation() * Vec3::new(direction.x, 0.0, direction.y);
+                (world.xz().normalize_or_zero(), 1.0)
+            }
+            None => (Vec2::ZERO, 0.0),
+        };
+
+        Self {
+            zone_from_world: transform.compute_matrix().inverse(),
+            extents,
+            direction,
+            direction_weight,
+            shape,
+            inner: zone.inner.clamp(0.0, 0.999),
+            strength: zone.strength,
+        }
+    }
+
+    pub fn distance(&self, position: Vec3) -> f32 {
+        let local_pos = self.zone_from_world.transform_point3(position);
+
+        match self.shape {
+            0 => local_pos.length() / self.extents.x,
+            1 => (local_pos.abs() / self.extents.xyz()).max_element(),
+            _ => {
+                let along = -local_pos.z;
+                if along < 0.0 {
+                    return 2.0;
+                }
+                let cone_radius = (along * self.extents.y).max(0.0001);
+                (along / self.extents.x).max(local_pos.xy().length() / cone_radius)
+            }
+        }
+    }
+
+    pub fn weight(&self, position: Vec3) -> f32 {
+        1.0 - smoothstep(self.inner, 1.0, self.distance(position))
+    }
+}
+
+pub(crate) fn collect_wind_zones(
+    mut zones: ResMut<WindZones>,
+    q: Query<(&WindZone, &GlobalTransform)>,
+    camera: Query<&GlobalTransform, With<Camera3d>>,
+) {
+    let origin = camera
+        .iter()
+        .next()
+        .map(GlobalTransform::translation)
+        .unwrap_or_default();
+
+    let mut values = q.iter().collect::<Vec<_>>();
+    values.sort_by(|(_, a), (_, b)| {
+        a.translation()
+            .distance_squared(origin)
+            .total_cmp(&b.translation().distance_squared(origin))
+    });
+
+    zones.set_if_neq(WindZones {
+        values: values
+            .into_iter()
+            .take(MAX_WIND_ZONES)
+            .map(|(zone, transform)| WindZoneUniform::new(zone, transform))
+            .collect(),
+    });
+}
+
+impl WindZones {
+    /// Applies the zones to `wind` at `position`, matching `apply_wind_zones` in `wind_displace.wgsl`.
+    pub fn apply(&self, wind: &Wind, position: Vec3) -> Wind {
+        let mut wind = wind.clone();
+        let mut strength_scale = 1.0;
+        let mut direction = wind.direction;
+        let mut direction_weight = 0.0;
+
+        for zone in self.values.iter().take(MAX_WIND_ZONES) {
+            let weight = zone.weight(position);
+            strength_scale *= 1.0 + (zone.strength - 1.0) * weight;
+            direction = direction.lerp(zone.direction, weight * zone.direction_weight);
+            direction_weight += weight * zone.direction_weight;
+        }
+
+        // Without a zone turning it, the direction keeps its length like outside of any zone.
+        if direction_weight > 0.0 && direction.length() > 0.0001 {
+            wind.direction = direction.normalize();
+        }
+        wind.strength *= strength_scale;
+        wind.micro_strength *= strength_scale;
+
+        wind
+    }
+}
+
+pub(crate) fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn zones(zone: WindZone) -> WindZones {
+        WindZones {
+            values: vec![WindZoneUniform::new(&zone, &GlobalTransform::IDENTITY)],
+        }
+    }
+
+    #[test]
+    fn outside_of_zones_wind_is_unchanged() {
+        let wind = Wind {
+            direction: Vec2::new(2.0, 0.0),
+            ..default()
+        };
+
+        assert_eq!(WindZones::default().apply(&wind, Vec3::ZERO), wind);
+        assert_eq!(
+            zones(WindZone::default()).apply(&wind, Vec3::new(10.0, 0.0, 0.0)),
+            wind
+        );
+    }
+
+    #[test]
+    fn strength_only_zones_keep_the_direction() {
+        let wind = Wind {
+            direction: Vec2::new(2.0, 0.0),
+            ..default()
+        };
+        let zoned = zones(WindZone {
+            strength: 2.0,
+            ..default()
+        })
+        .apply(&wind, Vec3::ZERO);
+
+        assert_eq!(zoned.direction, wind.direction);
+        assert_eq!(zoned.strength, wind.strength * 2.0);
+        assert_eq!(zoned.micro_strength, wind.micro_strength * 2.0);
+    }
+
+    #[test]
+    fn zones_turn_the_direction() {
+        let zoned = zones(WindZone {
+            direction: Some(Vec2::Y),
+            ..default()
+        })
+        .apply(&Wind::default(), Vec3::ZERO);
+
+        assert!(zoned.direction.abs_diff_eq(Vec2::Y, 0.0001));
+    }
+
+    #[test]
+    fn degenerate_shapes_stay_finite() {
+        for shape in [
+            WindZoneShape::Sphere { radius: 0.0 },
+            WindZoneShape::Box {
+                half_extents: Vec3::ZERO,
+            },
+            WindZoneShape::Cone {
+                angle: std::f32::consts::FRAC_PI_2,
+                length: 0.0,
+            },
+        ] {
+            let zone =
+                WindZoneUniform::new(&WindZone { shape, ..default() }, &GlobalTransform::IDENTITY);
+
+            for position in [Vec3::ZERO, Vec3::new(1.0, 2.0, -3.0)] {
+                assert!(zone.weight(position).is_finite(), "{shape:?} at {position}");
+            }
+        }
+    }
+}